@@ -14,17 +14,26 @@ async fn main() {
     let api_url = env::var("API_URL").unwrap();
     let client = Client::new(api_url).unwrap();
 
+    let new_house = NewHouse { name: "Дом для примера".to_string() };
+    let house = client.add_house(&new_house).await.unwrap();
+
     let new_room1 = NewRoom { name: "Новая комната 1".to_string() };
     let new_room2 = NewRoom { name: "Новая комната 2".to_string() };
 
-    let room1 = client.add_room(&new_room1).await.unwrap();
-    let room2 = client.add_room(&new_room2).await.unwrap();
+    let room1 = client.add_room(house.id, &new_room1).await.unwrap();
+    let room2 = client.add_room(house.id, &new_room2).await.unwrap();
 
-    let new_device1 = NewDevice { name: "Холодильник".to_string() };
+    let new_device1 = NewDevice {
+        name: "Холодильник".to_string(),
+        kind: DeviceKind::Socket { is_on: true, power: 150.0 }
+    };
     let device1 = client.add_device(room1.id, &new_device1).await.unwrap();
     println!("Device 1 {device1:?}");
 
-    let new_device2 = NewDevice { name: "Телевизор".to_string() };
+    let new_device2 = NewDevice {
+        name: "Термометр".to_string(),
+        kind: DeviceKind::Thermometer { temperature: 21.5 }
+    };
     let device2 = client.add_device(room2.id, &new_device2).await.unwrap();
     println!("Device 2 {device2:?}");
 
@@ -34,16 +43,32 @@ async fn main() {
     let room2_devices = client.get_devices(room2.id).await.unwrap();
     println!("Room 2 devices: {room2_devices:?}");
 
-    let new_device1 = NewDevice { name: "Холодильник 1".to_string() };
-    client.update_device(room1.id, device1.id, &new_device1).await.unwrap();
+    let device1_patch = DevicePatch { is_on: Some(false), ..Default::default() };
+    client.patch_device(room1.id, device1.id, &device1_patch, Some(&device1.etag())).await.unwrap();
     let device1 = client.get_device(room1.id, device1.id).await.unwrap();
     println!("Device 1 {device1:?}");
 
-    client.delete_device(room2.id, device2.id).await.unwrap();
+    let new_device1 = NewDevice {
+        name: "Холодильник 1".to_string(),
+        kind: DeviceKind::Socket { is_on: false, power: 0.0 }
+    };
+    client.replace_device(room1.id, device1.id, &new_device1, Some(&device1.etag())).await.unwrap();
+    let device1 = client.get_device(room1.id, device1.id).await.unwrap();
+    println!("Device 1 {device1:?}");
+
+    let device2 = client.move_device(room2.id, device2.id, room1.id).await.unwrap();
+    println!("Moved device 2 {device2:?}");
+
+    let room1_devices = client.get_devices(room1.id).await.unwrap();
+    println!("Room 1 devices: {room1_devices:?}");
+
+    client.delete_device(room1.id, device2.id, None).await.unwrap();
 
     let room2_devices = client.get_devices(room2.id).await.unwrap();
     println!("Room 2 devices: {room2_devices:?}");
 
-    client.delete_room(room1.id).await.unwrap();
-    client.delete_room(room2.id).await.unwrap();
+    client.delete_room(room1.id, None).await.unwrap();
+    client.delete_room(room2.id, None).await.unwrap();
+
+    client.delete_house(house.id).await.unwrap();
 }
@@ -17,15 +17,15 @@ async fn main() {
     // println!("get_house: {:?}", client.get_house().await.unwrap());
     // println!("get_rooms: {:?}", client.get_rooms().await.unwrap());
     // let new_room = NewRoom { name: "Новая команта".to_string() };
-    // println!("add_room: {:?}", client.add_room(&new_room).await.unwrap());
+    // println!("add_room: {:?}", client.add_room(&new_room, None).await.unwrap());
     // println!(
     //     "get_room(44cd078e-b693-41eb-af4b-8170cbf95f0c): {:?}",
     //     client.get_room("44cd078e-b693-41eb-af4b-8170cbf95f0c".parse().unwrap()).await.unwrap()
     // );
-    let new_room = NewRoom { name: "Новая команта 1".to_string() };
+    let room_patch = RoomPatch { name: Some("Новая команта 1".to_string()) };
     println!(
-        "update_room(44cd078e-b693-41eb-af4b-8170cbf95f0c): {:?}",
-        client.update_room("44cd078e-b693-41eb-af4b-8170cbf95f0c".parse().unwrap(), &new_room).await.unwrap()
+        "patch_room(44cd078e-b693-41eb-af4b-8170cbf95f0c): {:?}",
+        client.patch_room("44cd078e-b693-41eb-af4b-8170cbf95f0c".parse().unwrap(), &room_patch, None).await.unwrap()
     );
     // println!("get_devices(1): {}", client.get_devices("1".to_string()).await.unwrap());
     // println!("add_device(1): {}", client.add_device("1".to_string(), "Test".to_string()).await.unwrap());
@@ -0,0 +1,24 @@
+use std::env;
+
+use dotenv::dotenv;
+
+use client::Client;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    dotenv().unwrap();
+    env_logger::init();
+
+    let api_url = env::var("API_URL").unwrap();
+    let client = Client::new(api_url).unwrap();
+
+    println!("Version: {:?}", client.version().await.unwrap());
+    println!("Health: {:?}", client.health().await.unwrap());
+
+    let readiness = client.readiness().await.unwrap();
+    println!("Readiness: {:?}", readiness.status);
+
+    for check in readiness.checks {
+        println!("  {}: {:?} {}", check.name, check.status, check.message.unwrap_or_default());
+    }
+}
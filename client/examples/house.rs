@@ -2,6 +2,8 @@ use std::env;
 
 use dotenv::dotenv;
 
+use shared::*;
+
 use client::Client;
 
 #[tokio::main(flavor = "current_thread")]
@@ -12,7 +14,22 @@ async fn main() {
     let api_url = env::var("API_URL").unwrap();
     let client = Client::new(api_url).unwrap();
 
-    let house = client.get_house().await.unwrap();
+    let new_house = NewHouse { name: "Новый дом".to_string() };
+    let house = client.add_house(&new_house).await.unwrap();
+    println!("House: {house:?}");
+
+    let houses = client.get_houses().await.unwrap();
+    println!("Houses: {houses:?}");
 
+    let house_patch = HousePatch { name: Some("Обновленный новый дом".to_string()) };
+    let house = client.patch_house(house.id, &house_patch).await.unwrap();
+    println!("Updated house: {house:?}");
+
+    let house = client.get_house(house.id).await.unwrap();
     println!("House: {house:?}");
+
+    client.delete_house(house.id).await.unwrap();
+
+    let houses = client.get_houses().await.unwrap();
+    println!("Houses: {houses:?}");
 }
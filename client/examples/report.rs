@@ -0,0 +1,22 @@
+use std::env;
+
+use dotenv::dotenv;
+
+use client::Client;
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    dotenv().unwrap();
+    env_logger::init();
+
+    let api_url = env::var("API_URL").unwrap();
+    let client = Client::new(api_url).unwrap();
+
+    for house in client.get_houses().await.unwrap() {
+        let report = client.get_report(house.id).await.unwrap();
+        println!("Report: {report:?}");
+
+        let report = client.get_report_text(house.id).await.unwrap();
+        println!("{report}");
+    }
+}
@@ -1,7 +1,8 @@
-use std::env;
+use std::{env, pin::pin};
 
 use dotenv::dotenv;
 
+use futures::TryStreamExt;
 use shared::*;
 
 use client::Client;
@@ -14,33 +15,53 @@ async fn main() {
     let api_url = env::var("API_URL").unwrap();
     let client = Client::new(api_url).unwrap();
 
+    let new_house = NewHouse { name: "Дом для примера".to_string() };
+    let house = client.add_house(&new_house).await.unwrap();
+
     let new_room1 = NewRoom { name: "Новая комната 1".to_string() };
     let new_room2 = NewRoom { name: "Новая комната 2".to_string() };
 
-    let room1 = client.add_room(&new_room1).await.unwrap();
-    let room2 = client.add_room(&new_room2).await.unwrap();
+    let room1 = client.add_room(house.id, &new_room1).await.unwrap();
+    let room2 = client.add_room(house.id, &new_room2).await.unwrap();
 
     println!("Room1: {room1:?}");
     println!("Room2: {room2:?}");
 
-    let rooms = client.get_rooms().await.unwrap();
+    let rooms = client.get_rooms(house.id).await.unwrap();
     println!("Rooms: {rooms:?}");
 
-    let room_update = NewRoom { name: "Обновленная новая комната 1".to_string() };
-    let room1 = client.update_room(room1.id, &room_update).await.unwrap();
+    let query = ListQuery { sort: Some(SortBy::Name), limit: Some(1), ..Default::default() };
+    let mut rooms = pin!(client.rooms_stream(house.id, query));
+    while let Some(room) = rooms.try_next().await.unwrap() {
+        println!("Room by name, one per page: {room:?}");
+    }
+
+    let room_patch = RoomPatch { name: Some("Обновленная новая комната 1".to_string()) };
+    let room1 = client.patch_room(room1.id, &room_patch, Some(&room1.etag())).await.unwrap();
 
     println!("Updated room: {room1:?}");
 
-    let rooms = client.get_rooms().await.unwrap();
+    let query = ListQuery { updated_since: Some(room1.updated_at), ..Default::default() };
+    let updated_rooms = client.get_rooms_page(house.id, &query).await.unwrap().items;
+    println!("Rooms updated since {}: {updated_rooms:?}", room1.updated_at);
+
+    let rooms = client.get_rooms(house.id).await.unwrap();
     println!("Rooms: {rooms:?}");
 
-    client.delete_room(room2.id).await.unwrap();
+    let room_replacement = ReplaceRoom { house_id: house.id, name: "Замененная комната 2".to_string() };
+    let room2 = client.replace_room(room2.id, &room_replacement, Some(&room2.etag())).await.unwrap();
 
-    let rooms = client.get_rooms().await.unwrap();
+    println!("Replaced room: {room2:?}");
+
+    client.delete_room(room2.id, None).await.unwrap();
+
+    let rooms = client.get_rooms(house.id).await.unwrap();
     println!("Rooms: {rooms:?}");
 
-    client.delete_room(room1.id).await.unwrap();
+    client.delete_room(room1.id, None).await.unwrap();
 
-    let rooms = client.get_rooms().await.unwrap();
+    let rooms = client.get_rooms(house.id).await.unwrap();
     println!("Rooms: {rooms:?}");
+
+    client.delete_house(house.id).await.unwrap();
 }
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use http::{header, HeaderMap, HeaderName, HeaderValue};
+
+use crate::{parse_base_url, Client, ReqwestTransport, Result, RetryPolicy, Transport};
+
+/// Configures a [`Client`]. Without any options it matches [`Client::new`]:
+/// no timeouts and no retries.
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # fn main() -> client::Result<()> {
+/// let client = client::ClientBuilder::new("http://localhost:4000")
+///     .connect_timeout(Duration::from_secs(2))
+///     .timeout(Duration::from_secs(10))
+///     .auth_token("secret")
+///     .retries(3)
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ClientBuilder {
+    api_url: String,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    auth_token: Option<String>,
+    default_headers: HeaderMap,
+    retry: RetryPolicy,
+}
+
+impl ClientBuilder {
+    pub fn new(api_url: impl Into<String>) -> Self {
+        Self {
+            api_url: api_url.into(),
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+            auth_token: None,
+            default_headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+
+    /// Limit for establishing a connection, applies to the reqwest transport.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Limit for a whole request including the response body, applies to
+    /// the reqwest transport. Each retry gets its own.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sent as `Authorization: Bearer <token>`.
+    pub fn auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// Header added to every request unless the request sets it itself.
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+
+    /// Retries with the default backoff, see [`RetryPolicy`].
+    pub fn retries(mut self, max_retries: u32) -> Self {
+        self.retry.max_retries = max_retries;
+        self
+    }
+
+    pub fn retry_policy(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    /// Fails with [`Error::InvalidBaseUrl`](crate::Error::InvalidBaseUrl)
+    /// unless the base url is an absolute http(s) url.
+    pub fn build(self) -> Result<Client> {
+        let mut builder = reqwest::ClientBuilder::new();
+
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+
+        let transport = ReqwestTransport::from_client(builder.build()?);
+
+        self.build_with_transport(transport)
+    }
+
+    /// Client over another transport, the timeouts are left to it.
+    pub fn build_with_transport<T: Transport>(self, transport: T) -> Result<Client<T>> {
+        let api_url = parse_base_url(&self.api_url)?;
+        let mut default_headers = self.default_headers;
+
+        if let Some(user_agent) = self.user_agent {
+            default_headers.insert(header::USER_AGENT, HeaderValue::try_from(user_agent).map_err(http::Error::from)?);
+        }
+
+        if let Some(token) = self.auth_token {
+            let mut value = HeaderValue::try_from(format!("Bearer {token}")).map_err(http::Error::from)?;
+            value.set_sensitive(true);
+            default_headers.insert(header::AUTHORIZATION, value);
+        }
+
+        Ok(Client { api_url, transport, default_headers, retry: self.retry })
+    }
+}
@@ -2,10 +2,50 @@
 pub enum Error {
     #[error(transparent)]
     Reqwest(#[from] reqwest::Error),
+    /// Failure of a transport other than reqwest.
+    #[error("Transport error: {0}")]
+    Transport(tower::BoxError),
+    /// Base url given to the client isn't an absolute http(s) url.
+    #[error("Invalid base url {url:?}: {reason}")]
+    InvalidBaseUrl {
+        url: String,
+        reason: String
+    },
+    #[error("Invalid request: {0}")]
+    Request(#[from] http::Error),
+    #[error("Can't encode the query: {0}")]
+    Query(#[from] serde_urlencoded::ser::Error),
+    /// Request or response body isn't the expected JSON.
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
     #[error("Not found")]
     NotFound,
-    #[error("Server error")]
-    ServerError(Option<String>),
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+    /// Resource clashes with an existing one, e.g. a duplicate name.
+    #[error("Conflict: {message}")]
+    Conflict {
+        field: Option<String>,
+        message: String
+    },
+    /// Payload rejected by the server's validation rules.
+    #[error("Validation failed: {0:?}")]
+    Validation(Vec<shared::FieldError>),
+    /// Resource changed since the version passed as expected.
+    #[error("Precondition failed")]
+    PreconditionFailed,
+    /// Request body exceeds the server's limit.
+    #[error("Payload too large")]
+    PayloadTooLarge,
+    /// Server gave up processing the request.
+    #[error("Request timed out")]
+    Timeout,
+    /// Internal server error, `error_id` refers to the server log entry.
+    #[error("Server error{}", error_id.as_ref().map(|id| format!(" (error id {id})")).unwrap_or_default())]
+    ServerError {
+        message: Option<String>,
+        error_id: Option<String>
+    },
     #[error("Unexpected status {0}")]
     UnexpectedStatus(reqwest::StatusCode)
 }
@@ -1,94 +1,279 @@
+pub mod builder;
 pub mod error;
+pub mod retry;
+pub mod transport;
 
 use std::{fmt::Debug, result};
 
-use reqwest::StatusCode;
+use bytes::Bytes;
+use futures::{stream, Stream, TryStreamExt};
+use http::{header, HeaderMap, StatusCode};
+use url::Url;
 
 pub use shared::*;
 
+pub use builder::ClientBuilder;
 pub use error::Error;
+pub use retry::RetryPolicy;
+pub use transport::{ReqwestTransport, ServiceTransport, Transport};
 
 pub type Result<T> = result::Result<T, Error>;
 
-pub struct Client {
-    api_url: String,
-    client: reqwest::Client,
+pub struct Client<T = ReqwestTransport> {
+    api_url: Url,
+    transport: T,
+    default_headers: HeaderMap,
+    retry: RetryPolicy,
 }
 
 impl Client {
     pub fn new(api_url: String) -> Result<Self> {
-        let client = reqwest::ClientBuilder::new().build()?;
+        ClientBuilder::new(api_url).build()
+    }
+
+    /// Client with timeouts, retries or default headers.
+    pub fn builder(api_url: impl Into<String>) -> ClientBuilder {
+        ClientBuilder::new(api_url)
+    }
+}
+
+impl<T: Transport> Client<T> {
+    /// Client sending requests through `transport`, e.g. a [`ServiceTransport`]
+    /// over an in-process server.
+    pub fn with_transport(api_url: String, transport: T) -> Result<Self> {
+        ClientBuilder::new(api_url).build_with_transport(transport)
+    }
+
+    pub async fn get_houses(&self) -> Result<Vec<House>> {
+        self.get(&["houses"]).await
+    }
 
-        Ok(Self { api_url, client })
+    pub async fn add_house(&self, new_house: &NewHouse) -> Result<House> {
+        self.post(&["houses"], new_house).await
     }
 
-    pub async fn get_house(&self) -> Result<House> {
-        self.get("/").await
+    pub async fn get_house(&self, id: uuid::Uuid) -> Result<House> {
+        let path = ["houses", &id.to_string()];
+        self.get(&path).await
+    }
+
+    pub async fn patch_house(&self, id: uuid::Uuid, patch: &HousePatch) -> Result<House> {
+        let path = ["houses", &id.to_string()];
+        self.patch(&path, patch, None).await
+    }
+
+    /// Replaces the house, creating it at `id` if it does not exist.
+    pub async fn replace_house(&self, id: uuid::Uuid, house: &NewHouse) -> Result<House> {
+        let path = ["houses", &id.to_string()];
+        self.put(&path, house, None).await
+    }
+
+    pub async fn delete_house(&self, id: uuid::Uuid) -> Result<()> {
+        let path = ["houses", &id.to_string()];
+        self.delete(&path, None).await
     }
 
-    pub async fn get_rooms(&self) -> Result<Vec<Room>> {
-        self.get("/rooms").await
+    /// All rooms of the house, fetching every page.
+    pub async fn get_rooms(&self, house_id: uuid::Uuid) -> Result<Vec<Room>> {
+        self.rooms_stream(house_id, ListQuery::default()).try_collect().await
     }
 
-    pub async fn add_room<'a>(&self, new_room: &'a NewRoom) -> Result<Room> {
-        self.post("/rooms", new_room).await
+    pub async fn get_rooms_page(&self, house_id: uuid::Uuid, query: &ListQuery) -> Result<Page<Room>> {
+        let path = ["houses", &house_id.to_string(), "rooms"];
+        self.get_with_query(&path, query).await
+    }
+
+    /// Rooms matching `query`, pages are fetched lazily as the stream is polled.
+    pub fn rooms_stream(&self, house_id: uuid::Uuid, query: ListQuery) -> impl Stream<Item = Result<Room>> + '_ {
+        self.paginate(&["houses", &house_id.to_string(), "rooms"], query)
+    }
+
+    pub async fn add_room(&self, house_id: uuid::Uuid, new_room: &NewRoom) -> Result<Room> {
+        let path = ["houses", &house_id.to_string(), "rooms"];
+        self.post(&path, new_room).await
     }
 
     pub async fn get_room(&self, id: uuid::Uuid) -> Result<Room> {
-        let path = format!("/rooms/{id}");
+        let path = ["rooms", &id.to_string()];
         self.get(&path).await
     }
 
-    pub async fn update_room(&self, id: uuid::Uuid, room: &NewRoom) -> Result<Room> {
-        let path = format!("/rooms/{id}");
-        self.patch(&path, room).await
+    /// Updates the given room fields; with `expected_etag` (see [`Room::etag`])
+    /// fails with [`Error::PreconditionFailed`] if the room changed meanwhile.
+    pub async fn patch_room(&self, id: uuid::Uuid, patch: &RoomPatch, expected_etag: Option<&str>) -> Result<Room> {
+        let path = ["rooms", &id.to_string()];
+        self.patch(&path, patch, expected_etag).await
+    }
+
+    /// Replaces the room, creating it at `id` if it does not exist.
+    pub async fn replace_room(&self, id: uuid::Uuid, room: &ReplaceRoom, expected_etag: Option<&str>) -> Result<Room> {
+        let path = ["rooms", &id.to_string()];
+        self.put(&path, room, expected_etag).await
     }
 
-    pub async fn delete_room(&self, id: uuid::Uuid) -> Result<()> {
-        let path = format!("/rooms/{id}");
-        self.delete(&path).await
+    /// Deletes the room with its devices; with `expected_etag` fails with
+    /// [`Error::PreconditionFailed`] if the room changed meanwhile.
+    pub async fn delete_room(&self, id: uuid::Uuid, expected_etag: Option<&str>) -> Result<()> {
+        let path = ["rooms", &id.to_string()];
+        self.delete(&path, expected_etag).await
     }
 
+    /// All devices of the room, fetching every page.
     pub async fn get_devices(&self, room_id: uuid::Uuid) -> Result<Vec<Device>> {
-        let path = format!("/rooms/{room_id}/devices");
-        self.get(&path).await
+        self.devices_stream(room_id, ListQuery::default()).try_collect().await
+    }
+
+    pub async fn get_devices_page(&self, room_id: uuid::Uuid, query: &ListQuery) -> Result<Page<Device>> {
+        let path = ["rooms", &room_id.to_string(), "devices"];
+        self.get_with_query(&path, query).await
+    }
+
+    /// Devices matching `query`, pages are fetched lazily as the stream is polled.
+    pub fn devices_stream(&self, room_id: uuid::Uuid, query: ListQuery) -> impl Stream<Item = Result<Device>> + '_ {
+        self.paginate(&["rooms", &room_id.to_string(), "devices"], query)
     }
 
     pub async fn add_device(&self, room_id: uuid::Uuid, device: &NewDevice) -> Result<Device> {
-        let path = format!("/rooms/{room_id}/devices");
+        let path = ["rooms", &room_id.to_string(), "devices"];
         self.post(&path, device).await
     }
 
     pub async fn get_device(&self, room_id: uuid::Uuid, id: uuid::Uuid) -> Result<Device> {
-        let path = format!("/rooms/{room_id}/devices/{id}");
+        let path = ["rooms", &room_id.to_string(), "devices", &id.to_string()];
+        self.get(&path).await
+    }
+
+    /// Updates the given device fields; with `expected_etag` (see [`Device::etag`])
+    /// fails with [`Error::PreconditionFailed`] if the device changed meanwhile.
+    pub async fn patch_device(
+        &self,
+        room_id: uuid::Uuid,
+        id: uuid::Uuid,
+        patch: &DevicePatch,
+        expected_etag: Option<&str>
+    ) -> Result<Device> {
+        let path = ["rooms", &room_id.to_string(), "devices", &id.to_string()];
+        self.patch(&path, patch, expected_etag).await
+    }
+
+    /// Replaces the device, creating it at `id` if it does not exist.
+    pub async fn replace_device(
+        &self,
+        room_id: uuid::Uuid,
+        id: uuid::Uuid,
+        device: &NewDevice,
+        expected_etag: Option<&str>
+    ) -> Result<Device> {
+        let path = ["rooms", &room_id.to_string(), "devices", &id.to_string()];
+        self.put(&path, device, expected_etag).await
+    }
+
+    /// Moves the device to another room, keeping its id.
+    pub async fn move_device(&self, room_id: uuid::Uuid, id: uuid::Uuid, target_room_id: uuid::Uuid) -> Result<Device> {
+        let path = ["rooms", &room_id.to_string(), "devices", &id.to_string(), "move"];
+        self.post(&path, MoveDevice { room_id: target_room_id }).await
+    }
+
+    /// With `expected_etag` fails with [`Error::PreconditionFailed`] if the
+    /// device changed meanwhile.
+    pub async fn delete_device(&self, room_id: uuid::Uuid, id: uuid::Uuid, expected_etag: Option<&str>) -> Result<()> {
+        let path = ["rooms", &room_id.to_string(), "devices", &id.to_string()];
+        self.delete(&path, expected_etag).await
+    }
+
+    pub async fn get_report(&self, house_id: uuid::Uuid) -> Result<Report> {
+        let path = ["houses", &house_id.to_string(), "report"];
         self.get(&path).await
     }
 
-    pub async fn update_device(&self, room_id: uuid::Uuid, id: uuid::Uuid, payload: &NewDevice) -> Result<Device> {
-        let path = format!("/rooms/{room_id}/devices/{id}");
-        self.patch(&path, payload).await
+    /// Plain-text rendering of the report, as produced by the server.
+    pub async fn get_report_text(&self, house_id: uuid::Uuid) -> Result<String> {
+        let url = self.make_url(&["houses", &house_id.to_string(), "report"]);
+        log::debug!("Request: GET {url} (text/plain)");
+
+        let request = http::Request::get(url.as_str()).header(header::ACCEPT, "text/plain");
+        let response = self.send(request, Bytes::new()).await?;
+
+        match response.status() {
+            StatusCode::OK => Ok(String::from_utf8_lossy(response.body()).into_owned()),
+            _ => handle_response(response)
+        }
     }
 
-    pub async fn delete_device(&self, room_id: uuid::Uuid, id: uuid::Uuid) -> Result<()> {
-        let path = format!("/rooms/{room_id}/devices/{id}");
-        self.delete(&path).await
+    /// Liveness of the server process.
+    pub async fn health(&self) -> Result<Health> {
+        self.get(&["healthz"]).await
     }
 
-    pub async fn get_report(&self) -> Result<String> {
-        self.get("/report").await
+    /// Readiness to serve requests, a not ready server answers with
+    /// [`HealthStatus::Unavailable`] rather than an error.
+    pub async fn readiness(&self) -> Result<Health> {
+        let url = self.make_url(&["readyz"]);
+        log::debug!("Request: GET {url}");
+
+        let response = self.send(http::Request::get(url.as_str()), Bytes::new()).await?;
+
+        match response.status() {
+            StatusCode::SERVICE_UNAVAILABLE => serde_json::from_slice(response.body()).map_err(Into::into),
+            _ => handle_response(response)
+        }
     }
 
-    async fn get<R: serde::de::DeserializeOwned>(&self, path: &str) -> Result<R> {
+    pub async fn version(&self) -> Result<Version> {
+        self.get(&["version"]).await
+    }
+
+    /// The stream owns a copy of `path`, so it only borrows the client.
+    fn paginate<'a, E>(&'a self, path: &[&str], query: ListQuery) -> impl Stream<Item = Result<E>> + use<'a, T, E>
+    where
+        E: serde::de::DeserializeOwned + 'static
+    {
+        let path: Vec<String> = path.iter().map(ToString::to_string).collect();
+
+        stream::try_unfold((path, Some(query)), move |(path, query)| async move {
+            let Some(query) = query else {
+                return Result::Ok(None);
+            };
+
+            let segments: Vec<&str> = path.iter().map(String::as_str).collect();
+            let page: Page<E> = self.get_with_query(&segments, &query).await?;
+
+            let next_query = page.next_cursor.map(|cursor| ListQuery { cursor: Some(cursor), ..query });
+            let items = stream::iter(page.items.into_iter().map(Ok));
+
+            Ok(Some((items, (path, next_query))))
+        })
+            .try_flatten()
+    }
+
+    async fn get<R: serde::de::DeserializeOwned>(&self, path: &[&str]) -> Result<R> {
         let url = self.make_url(path);
         log::debug!("Request: GET {url}");
 
-        let response = self.client.get(url).send().await?;
-        log::debug!("Response: {response:?}");
+        let response = self.send(http::Request::get(url.as_str()), Bytes::new()).await?;
 
-        handle_response(response).await
+        handle_response(response)
     }
 
-    async fn post<P, R>(&self, path: &str, payload: P) -> Result<R>
+    async fn get_with_query<Q, R>(&self, path: &[&str], query: &Q) -> Result<R>
+    where
+        Q: serde::ser::Serialize + Debug,
+        R: serde::de::DeserializeOwned
+    {
+        let mut url = self.make_url(path);
+        log::debug!("Request: GET {url} with {query:?}");
+
+        let query = serde_urlencoded::to_string(query)?;
+        if !query.is_empty() {
+            url.set_query(Some(&query));
+        }
+
+        let response = self.send(http::Request::get(url.as_str()), Bytes::new()).await?;
+
+        handle_response(response)
+    }
+
+    async fn post<P, R>(&self, path: &[&str], payload: P) -> Result<R>
     where
         P: serde::ser::Serialize + Debug,
         R: serde::de::DeserializeOwned
@@ -96,61 +281,189 @@ impl Client {
         let url = self.make_url(path);
         log::debug!("Request: POST {url} with {payload:?}");
 
-        let response = self.client.post(url).json(&payload).send().await?;
-        log::debug!("Response: {response:?}");
+        let response = self.send_json(http::Request::post(url.as_str()), &payload).await?;
 
-        handle_response(response).await
+        handle_response(response)
     }
 
-    async fn patch<P, R>(&self, path: &str, payload: P) -> Result<R>
+    async fn patch<P, R>(&self, path: &[&str], payload: P, if_match: Option<&str>) -> Result<R>
     where
         P: serde::ser::Serialize + Debug,
         R: serde::de::DeserializeOwned
     {
         let url = self.make_url(path);
-        log::debug!("Request: PATCH {url} with {payload:?}");
+        log::debug!("Request: PATCH {url} with {payload:?}, If-Match: {if_match:?}");
+
+        let mut request = http::Request::patch(url.as_str());
 
-        let response = self.client.patch(url).json(&payload).send().await?;
-        log::debug!("Response: {response:?}");
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+
+        let response = self.send_json(request, &payload).await?;
 
-        handle_response(response).await
+        handle_response(response)
     }
 
-    async fn delete(&self, path: &str) -> Result<()> {
+    async fn put<P, R>(&self, path: &[&str], payload: P, if_match: Option<&str>) -> Result<R>
+    where
+        P: serde::ser::Serialize + Debug,
+        R: serde::de::DeserializeOwned
+    {
         let url = self.make_url(path);
-        log::debug!("Request: DELETE {url}");
+        log::debug!("Request: PUT {url} with {payload:?}, If-Match: {if_match:?}");
+
+        let mut request = http::Request::put(url.as_str());
+
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+
+        let response = self.send_json(request, &payload).await?;
 
-        let response = self.client.delete(url).send().await?;
-        log::debug!("Response: {response:?}");
+        handle_response(response)
+    }
+
+    async fn delete(&self, path: &[&str], if_match: Option<&str>) -> Result<()> {
+        let url = self.make_url(path);
+        log::debug!("Request: DELETE {url}, If-Match: {if_match:?}");
+
+        let mut request = http::Request::delete(url.as_str());
+
+        if let Some(if_match) = if_match {
+            request = request.header(header::IF_MATCH, if_match);
+        }
+
+        let response = self.send(request, Bytes::new()).await?;
 
         match response.status() {
             StatusCode::NO_CONTENT => Ok(()),
-            StatusCode::NOT_FOUND => Err(Error::NotFound),
-            StatusCode::INTERNAL_SERVER_ERROR => {
-                match response.json::<shared::Error>().await {
-                    Ok(error) => Err(Error::ServerError(Some(error.error))),
-                    Err(error) => Err(error.into())
+            _ => Err(error_from_response(response))
+        }
+    }
+
+    async fn send_json<P>(&self, request: http::request::Builder, payload: &P) -> Result<transport::Response>
+    where
+        P: serde::ser::Serialize
+    {
+        let body = serde_json::to_vec(payload)?;
+        let request = request.header(header::CONTENT_TYPE, "application/json");
+
+        self.send(request, body.into()).await
+    }
+
+    async fn send(&self, request: http::request::Builder, body: Bytes) -> Result<transport::Response> {
+        let mut request = request.body(body)?;
+
+        for (name, value) in &self.default_headers {
+            if !request.headers().contains_key(name) {
+                request.headers_mut().insert(name, value.clone());
+            }
+        }
+
+        let retries = self.retry.retries_for(request.method());
+        let mut retry = 0;
+
+        loop {
+            let result = self.transport.send(copy_request(&request)).await;
+
+            match retry::retry_reason(&result) {
+                Some(reason) if retry < retries => {
+                    let backoff = self.retry.backoff(retry);
+                    log::warn!("{} {} failed with {reason}, retrying in {backoff:?}", request.method(), request.uri());
+
+                    tokio::time::sleep(backoff).await;
+                    retry += 1;
+                }
+                _ => {
+                    let response = result?;
+                    log::debug!("Response: {response:?}");
+
+                    return Ok(response);
                 }
-            },
-            _ => Err(Error::UnexpectedStatus(response.status()))
+            }
         }
     }
 
-    fn make_url(&self, path: &str) -> String {
-        format!("{}{path}", self.api_url)
+    /// Url of the `path` segments under the base url, each one is
+    /// percent-encoded, so a `/` in a value can't add a segment.
+    fn make_url(&self, path: &[&str]) -> Url {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut()
+            .expect("base url is checked at construction")
+            .pop_if_empty()
+            .extend(path);
+
+        url
+    }
+}
+
+/// Parses the base url of the API. Its path, e.g. `/api/v1` behind a
+/// reverse proxy, prefixes every request path.
+fn parse_base_url(api_url: &str) -> Result<Url> {
+    let invalid = |reason: String| Error::InvalidBaseUrl { url: api_url.to_string(), reason };
+
+    let url = Url::parse(api_url).map_err(|err| invalid(err.to_string()))?;
+
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid(format!("unsupported scheme {}", url.scheme())));
+    }
+
+    if url.query().is_some() || url.fragment().is_some() {
+        return Err(invalid("query and fragment are not allowed".to_string()));
     }
+
+    Ok(url)
+}
+
+/// Copy of the request for one attempt, the body is reference counted.
+fn copy_request(request: &transport::Request) -> transport::Request {
+    let mut copy = http::Request::new(request.body().clone());
+    *copy.method_mut() = request.method().clone();
+    *copy.uri_mut() = request.uri().clone();
+    *copy.version_mut() = request.version();
+    *copy.headers_mut() = request.headers().clone();
+
+    copy
 }
 
-async fn handle_response<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T> {
+fn handle_response<T: serde::de::DeserializeOwned>(response: transport::Response) -> Result<T> {
     match response.status() {
-        StatusCode::OK | StatusCode::CREATED => response.json::<T>().await.map_err(Into::into),
-        StatusCode::NOT_FOUND => Err(Error::NotFound),
-        StatusCode::INTERNAL_SERVER_ERROR => {
-            match response.json::<shared::Error>().await {
-                Ok(error) => Err(Error::ServerError(Some(error.error))),
-                Err(error) => Err(error.into())
-            }
-        },
-        _ => Err(Error::UnexpectedStatus(response.status()))
+        StatusCode::OK | StatusCode::CREATED => serde_json::from_slice(response.body()).map_err(Into::into),
+        _ => Err(error_from_response(response))
+    }
+}
+
+fn error_from_response(response: transport::Response) -> Error {
+    let status = response.status();
+
+    match status {
+        StatusCode::NOT_FOUND => return Error::NotFound,
+        StatusCode::PRECONDITION_FAILED => return Error::PreconditionFailed,
+        StatusCode::PAYLOAD_TOO_LARGE => return Error::PayloadTooLarge,
+        StatusCode::GATEWAY_TIMEOUT => return Error::Timeout,
+        _ => {}
+    }
+
+    if !matches!(
+        status,
+        StatusCode::BAD_REQUEST
+            | StatusCode::CONFLICT
+            | StatusCode::UNPROCESSABLE_ENTITY
+            | StatusCode::INTERNAL_SERVER_ERROR
+    ) {
+        return Error::UnexpectedStatus(status);
+    }
+
+    let error = match serde_json::from_slice::<shared::Error>(response.body()) {
+        Ok(error) => error,
+        Err(error) => return error.into()
+    };
+
+    match status {
+        StatusCode::BAD_REQUEST => Error::BadRequest(error.error),
+        StatusCode::CONFLICT => Error::Conflict { field: error.field, message: error.error },
+        StatusCode::UNPROCESSABLE_ENTITY => Error::Validation(error.errors),
+        _ => Error::ServerError { message: Some(error.error), error_id: error.error_id }
     }
 }
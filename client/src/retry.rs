@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+use http::Method;
+
+use crate::{transport::Response, Error, Result};
+
+/// Retries of idempotent requests (GET, PUT and DELETE) that failed with
+/// a 5xx status or a connection error, with exponential backoff and jitter.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Attempts after the first one, none by default.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each next one.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_retries: u32) -> Self {
+        Self { max_retries, ..Self::default() }
+    }
+
+    /// Number of retries allowed for a request of `method`.
+    pub(crate) fn retries_for(&self, method: &Method) -> u32 {
+        if matches!(*method, Method::GET | Method::PUT | Method::DELETE) {
+            self.max_retries
+        } else {
+            0
+        }
+    }
+
+    /// Delay before retry number `retry`, counting from zero. Jitter picks a
+    /// point in the upper half of the exponential delay, so clients that
+    /// failed together don't retry together.
+    pub(crate) fn backoff(&self, retry: u32) -> Duration {
+        let delay = self.initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let half = delay / 2;
+
+        half + half.mul_f64(fastrand::f64())
+    }
+}
+
+/// Why the attempt is worth repeating, `None` if it isn't.
+pub(crate) fn retry_reason(result: &Result<Response>) -> Option<String> {
+    match result {
+        Ok(response) if response.status().is_server_error() => Some(format!("status {}", response.status())),
+        Err(Error::Reqwest(err)) if err.is_connect() => Some(err.to_string()),
+        _ => None
+    }
+}
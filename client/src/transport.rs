@@ -0,0 +1,89 @@
+// How requests reach the server. `Client` builds plain `http` requests with
+// buffered bodies and leaves sending them to a transport.
+
+use std::future::Future;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use tower::{BoxError, Service, ServiceExt};
+
+use crate::{Error, Result};
+
+pub type Request = http::Request<Bytes>;
+
+/// Response with the whole body read.
+pub type Response = http::Response<Bytes>;
+
+pub trait Transport: Send + Sync {
+    fn send(&self, request: Request) -> impl Future<Output = Result<Response>> + Send;
+}
+
+/// Sends requests over the network, the default transport.
+#[derive(Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new() -> Result<Self> {
+        let client = reqwest::ClientBuilder::new().build()?;
+
+        Ok(Self { client })
+    }
+
+    /// Transport over an already configured client.
+    pub fn from_client(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    async fn send(&self, request: Request) -> Result<Response> {
+        let response = self.client.execute(request.try_into()?).await?;
+
+        let status = response.status();
+        let headers = response.headers().clone();
+
+        let mut result = http::Response::new(response.bytes().await?);
+        *result.status_mut() = status;
+        *result.headers_mut() = headers;
+
+        Ok(result)
+    }
+}
+
+/// Calls a `tower::Service` directly, e.g. the server's axum `Router`,
+/// so client and server can run in one process without a socket.
+#[derive(Clone)]
+pub struct ServiceTransport<S> {
+    service: S,
+}
+
+impl<S> ServiceTransport<S> {
+    pub fn new(service: S) -> Self {
+        Self { service }
+    }
+}
+
+impl<S, B> Transport for ServiceTransport<S>
+where
+    S: Service<http::Request<Full<Bytes>>, Response = http::Response<B>> + Clone + Send + Sync,
+    S::Future: Send,
+    S::Error: Into<BoxError>,
+    B: http_body::Body + Send,
+    B::Data: Send,
+    B::Error: Into<BoxError>,
+{
+    async fn send(&self, request: Request) -> Result<Response> {
+        let response = self.service
+            .clone()
+            .oneshot(request.map(Full::new))
+            .await
+            .map_err(|err| Error::Transport(err.into()))?;
+
+        let (parts, body) = response.into_parts();
+        let body = body.collect().await.map_err(|err| Error::Transport(err.into()))?.to_bytes();
+
+        Ok(http::Response::from_parts(parts, body))
+    }
+}
@@ -0,0 +1,149 @@
+// Client behaviour against canned responses, no server involved.
+
+use std::{
+    convert::Infallible,
+    sync::{Arc, Mutex},
+    time::Duration
+};
+
+use bytes::Bytes;
+use client::{Client, ClientBuilder, Error, HousePatch, NewHouse, RetryPolicy, ServiceTransport, Transport};
+use http::{header, HeaderMap, HeaderName, HeaderValue, Method, Request, Response, StatusCode};
+use http_body_util::Full;
+use tower::service_fn;
+
+const API_URL: &str = "http://localhost";
+
+/// Client whose every request is answered with `status` and the JSON `body`.
+fn responding(status: StatusCode, body: &'static str) -> Client<impl Transport> {
+    let service = service_fn(move |_| async move {
+        let response = Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(Full::new(Bytes::from_static(body.as_bytes())))
+            .unwrap();
+
+        Ok::<_, Infallible>(response)
+    });
+
+    Client::with_transport(API_URL.to_string(), ServiceTransport::new(service)).unwrap()
+}
+
+/// Method and headers of every request a [`flaky`] service received.
+type Received = Arc<Mutex<Vec<(Method, HeaderMap)>>>;
+
+/// Service answering the first `failures` requests with 503, the later ones
+/// with 200 and the JSON `body`.
+fn flaky(failures: usize, body: &'static str) -> (impl Transport, Received) {
+    let received = Received::default();
+
+    let service = service_fn({
+        let received = received.clone();
+        move |request: Request<Full<Bytes>>| {
+            let attempt = {
+                let mut received = received.lock().unwrap();
+                received.push((request.method().clone(), request.headers().clone()));
+                received.len()
+            };
+
+            async move {
+                let status = if attempt > failures { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+                Ok::<_, Infallible>(Response::builder().status(status).body(Full::new(Bytes::from_static(body.as_bytes()))).unwrap())
+            }
+        }
+    });
+
+    (ServiceTransport::new(service), received)
+}
+
+/// Retries without waiting, so the tests stay fast.
+fn retrying(max_retries: u32) -> ClientBuilder {
+    let policy = RetryPolicy { max_retries, initial_backoff: Duration::from_millis(1), max_backoff: Duration::from_millis(1) };
+
+    ClientBuilder::new(API_URL).retry_policy(policy)
+}
+
+fn attempts(received: &Received) -> usize {
+    received.lock().unwrap().len()
+}
+
+const HOUSE: &str = r#"{"id":"00000000-0000-0000-0000-000000000001","name":"Home","created_at":"2025-01-01T00:00:00Z","updated_at":"2025-01-01T00:00:00Z"}"#;
+
+#[tokio::test]
+async fn retries_idempotent_requests_on_server_errors() {
+    let id = uuid::Uuid::nil();
+    let house = NewHouse { name: "Home".to_string() };
+
+    let (transport, received) = flaky(usize::MAX, HOUSE);
+    let client = retrying(2).build_with_transport(transport).unwrap();
+
+    assert!(matches!(client.get_houses().await, Err(Error::UnexpectedStatus(StatusCode::SERVICE_UNAVAILABLE))));
+    assert_eq!(attempts(&received), 3);
+
+    assert!(client.replace_house(id, &house).await.is_err());
+    assert!(client.delete_house(id).await.is_err());
+
+    let methods: Vec<_> = received.lock().unwrap().iter().map(|(method, _)| method.clone()).collect();
+    assert_eq!(methods, [[Method::GET; 3], [Method::PUT; 3], [Method::DELETE; 3]].concat());
+}
+
+#[tokio::test]
+async fn succeeds_once_a_retry_gets_through() {
+    let (transport, received) = flaky(2, HOUSE);
+    let client = retrying(3).build_with_transport(transport).unwrap();
+
+    let house = client.get_house(uuid::Uuid::nil()).await.unwrap();
+    assert_eq!(house.name, "Home");
+    assert_eq!(attempts(&received), 3);
+}
+
+#[tokio::test]
+async fn does_not_retry_post_and_patch() {
+    let (transport, received) = flaky(usize::MAX, HOUSE);
+    let client = retrying(3).build_with_transport(transport).unwrap();
+
+    assert!(client.add_house(&NewHouse { name: "Home".to_string() }).await.is_err());
+    assert!(client.patch_house(uuid::Uuid::nil(), &HousePatch::default()).await.is_err());
+
+    let methods: Vec<_> = received.lock().unwrap().iter().map(|(method, _)| method.clone()).collect();
+    assert_eq!(methods, [Method::POST, Method::PATCH]);
+}
+
+#[tokio::test]
+async fn does_not_retry_by_default() {
+    let (transport, received) = flaky(usize::MAX, HOUSE);
+    let client = Client::with_transport(API_URL.to_string(), transport).unwrap();
+
+    assert!(client.get_houses().await.is_err());
+    assert_eq!(attempts(&received), 1);
+}
+
+#[tokio::test]
+async fn sends_default_and_auth_headers() {
+    let (transport, received) = flaky(1, "[]");
+    let client = retrying(1)
+        .user_agent("tests/1.0")
+        .auth_token("secret")
+        .default_header(HeaderName::from_static("x-tenant"), HeaderValue::from_static("acme"))
+        .build_with_transport(transport)
+        .unwrap();
+
+    client.get_houses().await.unwrap();
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 2);
+
+    for (_, headers) in received.iter() {
+        assert_eq!(headers[header::USER_AGENT], "tests/1.0");
+        assert_eq!(headers[header::AUTHORIZATION], "Bearer secret");
+        assert_eq!(headers["x-tenant"], "acme");
+    }
+}
+
+#[tokio::test]
+async fn decodes_errors_with_unknown_or_missing_code() {
+    for body in [r#"{"error":"Name is taken","code":"added_later"}"#, r#"{"error":"Name is taken"}"#] {
+        let err = responding(StatusCode::CONFLICT, body).get_houses().await.unwrap_err();
+        assert!(matches!(err, Error::Conflict { ref message, .. } if message == "Name is taken"), "{err:?}");
+    }
+}
@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE devices
+	DROP CONSTRAINT IF EXISTS devices_kind_check,
+	DROP COLUMN IF EXISTS kind,
+	DROP COLUMN IF EXISTS is_on,
+	DROP COLUMN IF EXISTS power,
+	DROP COLUMN IF EXISTS temperature;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+
+ALTER TABLE devices
+	ADD COLUMN kind varchar NOT NULL DEFAULT 'socket',
+	ADD COLUMN is_on boolean,
+	ADD COLUMN power double precision,
+	ADD COLUMN temperature double precision;
+
+-- Existing name-only devices become switched off sockets
+UPDATE devices SET is_on = false, power = 0;
+
+ALTER TABLE devices ALTER COLUMN kind DROP DEFAULT;
+
+ALTER TABLE devices ADD CONSTRAINT devices_kind_check CHECK (
+	(kind = 'socket' AND is_on IS NOT NULL AND power IS NOT NULL AND temperature IS NULL) OR
+	(kind = 'thermometer' AND temperature IS NOT NULL AND is_on IS NULL AND power IS NULL)
+);
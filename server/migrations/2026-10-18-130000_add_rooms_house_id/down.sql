@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+ALTER TABLE rooms DROP CONSTRAINT IF EXISTS rooms_house_id_name_unique;
+ALTER TABLE rooms ADD CONSTRAINT room_name_unique UNIQUE (name);
+ALTER TABLE rooms DROP COLUMN IF EXISTS house_id;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+
+ALTER TABLE rooms ADD COLUMN house_id uuid REFERENCES houses(id) ON DELETE CASCADE;
+
+-- Rooms of existing single-house deployments go to the oldest house
+INSERT INTO houses (name)
+	SELECT 'House' WHERE NOT EXISTS (SELECT 1 FROM houses) AND EXISTS (SELECT 1 FROM rooms);
+
+UPDATE rooms SET house_id = (SELECT id FROM houses ORDER BY created_at LIMIT 1);
+
+ALTER TABLE rooms ALTER COLUMN house_id SET NOT NULL;
+
+ALTER TABLE rooms DROP CONSTRAINT room_name_unique;
+ALTER TABLE rooms ADD CONSTRAINT rooms_house_id_name_unique UNIQUE (house_id, name);
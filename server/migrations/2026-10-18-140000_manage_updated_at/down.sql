@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TRIGGER IF EXISTS set_updated_at ON devices;
+DROP TRIGGER IF EXISTS set_updated_at ON rooms;
+DROP TRIGGER IF EXISTS set_updated_at ON houses;
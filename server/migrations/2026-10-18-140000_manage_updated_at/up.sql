@@ -0,0 +1,5 @@
+-- Your SQL goes here
+
+SELECT diesel_manage_updated_at('houses');
+SELECT diesel_manage_updated_at('rooms');
+SELECT diesel_manage_updated_at('devices');
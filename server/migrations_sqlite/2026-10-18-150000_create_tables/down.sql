@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+
+DROP TABLE IF EXISTS devices;
+DROP TABLE IF EXISTS rooms;
+DROP TABLE IF EXISTS houses;
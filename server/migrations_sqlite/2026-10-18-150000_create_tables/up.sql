@@ -0,0 +1,40 @@
+-- SQLite has no uuid type, no gen_random_uuid() and no plpgsql triggers:
+-- ids are UUID strings and timestamps are set by the application.
+
+CREATE TABLE houses (
+	id text NOT NULL,
+	name text NOT NULL,
+	created_at timestamp NOT NULL,
+	updated_at timestamp NOT NULL,
+	CONSTRAINT houses_pk PRIMARY KEY (id),
+	CONSTRAINT houses_name_unique UNIQUE (name)
+);
+
+CREATE TABLE rooms (
+	id text NOT NULL,
+	house_id text NOT NULL REFERENCES houses(id) ON DELETE CASCADE,
+	name text NOT NULL,
+	created_at timestamp NOT NULL,
+	updated_at timestamp NOT NULL,
+	CONSTRAINT room_pk PRIMARY KEY (id),
+	CONSTRAINT rooms_house_id_name_unique UNIQUE (house_id, name)
+);
+
+CREATE TABLE devices (
+	id text NOT NULL,
+	room_id text NOT NULL REFERENCES rooms(id) ON DELETE CASCADE,
+	name text NOT NULL,
+	kind text NOT NULL,
+	is_on boolean,
+	power double precision,
+	temperature double precision,
+	created_at timestamp NOT NULL,
+	updated_at timestamp NOT NULL,
+	CONSTRAINT device_pk PRIMARY KEY (id),
+	CONSTRAINT devices_kind_check CHECK (
+		(kind = 'socket' AND is_on IS NOT NULL AND power IS NOT NULL AND temperature IS NULL) OR
+		(kind = 'thermometer' AND temperature IS NOT NULL AND is_on IS NULL AND power IS NULL)
+	)
+);
+
+CREATE UNIQUE INDEX index_devices_on_room_id_and_name ON devices (room_id, name);
@@ -0,0 +1,191 @@
+use std::{net::SocketAddr, path::PathBuf};
+
+use clap::{
+    builder::BoolishValueParser,
+    error::ErrorKind,
+    parser::ValueSource,
+    ArgMatches, Args, CommandFactory, FromArgMatches, Parser, Subcommand
+};
+
+use crate::config::{LogFormat, Storage};
+
+/// Smart house API server.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// TOML config file, its settings are overridden by environment and arguments.
+    #[arg(long, global = true, env = "CONFIG_FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Print the resolved configuration with secrets redacted and exit.
+    #[arg(long, global = true)]
+    pub print_config: bool,
+
+    /// Log filter in `RUST_LOG` syntax.
+    #[arg(long, global = true, env = "RUST_LOG")]
+    pub log_level: Option<String>,
+
+    /// Log line format.
+    #[arg(long, global = true, env = "LOG_FORMAT")]
+    pub log_format: Option<LogFormat>,
+
+    /// Database connection url, `sqlite://path` selects SQLite.
+    #[arg(long, global = true, env = "DATABASE_URL", hide_env_values = true)]
+    pub database_url: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    #[command(flatten)]
+    pub serve: ServeArgs,
+}
+
+impl Cli {
+    /// Parses the command line like [`Parser::parse`]. Serve flags given
+    /// before the `serve` subcommand are merged into its own, before any
+    /// other subcommand they are rejected instead of being ignored.
+    pub fn parse_args() -> Self {
+        let mut command = Self::command();
+        let matches = command.get_matches_mut();
+        let mut cli = Self::from_arg_matches(&matches).unwrap_or_else(|err| err.format(&mut command).exit());
+
+        match (&mut cli.command, matches.subcommand()) {
+            (Some(Command::Serve(args)), Some((_, serve_matches))) => {
+                let top_level = std::mem::take(&mut cli.serve);
+                args.merge(top_level, |id| on_command_line(&matches, id) && !on_command_line(serve_matches, id));
+            },
+            (Some(_), _) => {
+                if let Some(id) = serve_arg_ids().into_iter().find(|id| on_command_line(&matches, id)) {
+                    let message = format!(
+                        "--{} only applies to serving, it can't be used with a subcommand other than serve",
+                        id.replace('_', "-")
+                    );
+                    command.error(ErrorKind::ArgumentConflict, message).exit();
+                }
+            },
+            (None, _) => {}
+        }
+
+        cli
+    }
+}
+
+fn serve_arg_ids() -> Vec<String> {
+    ServeArgs::augment_args(clap::Command::new("serve"))
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .collect()
+}
+
+/// Whether the argument was passed on the command line rather than through
+/// the environment.
+fn on_command_line(matches: &ArgMatches, id: &str) -> bool {
+    matches.value_source(id) == Some(ValueSource::CommandLine)
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Serve the HTTP API (the default command).
+    Serve(ServeArgs),
+    /// Manage the database schema with the embedded migrations.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Create a demo house with a few rooms and devices.
+    Seed,
+    /// Write houses with their rooms and devices as JSON.
+    Export {
+        /// Houses to export, all of them when omitted.
+        house_ids: Vec<uuid::Uuid>,
+        /// Output file, stdout when omitted.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Create houses with their rooms and devices from an export.
+    Import {
+        /// Input file, stdin when omitted.
+        #[arg(short, long)]
+        input: Option<PathBuf>,
+    },
+}
+
+/// Serve settings, unset ones fall back to the config file.
+#[derive(clap::Args, Default)]
+pub struct ServeArgs {
+    /// Address to listen on.
+    #[arg(long, env = "API_HOST")]
+    pub bind: Option<SocketAddr>,
+
+    /// Where to keep houses, rooms and devices.
+    #[arg(long, env = "STORAGE")]
+    pub storage: Option<Storage>,
+
+    /// Apply pending migrations before serving instead of refusing to start.
+    #[arg(
+        long,
+        env = "AUTO_MIGRATE",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "true",
+        value_parser = BoolishValueParser::new()
+    )]
+    pub auto_migrate: Option<bool>,
+
+    /// Maximum number of database connections.
+    #[arg(long, env = "DB_POOL_SIZE")]
+    pub pool_size: Option<u32>,
+
+    /// How many times to retry connecting to the database at startup.
+    #[arg(long, env = "DB_CONNECT_RETRIES")]
+    pub connect_retries: Option<u32>,
+
+    /// Maximum request body size in bytes.
+    #[arg(long, env = "BODY_LIMIT")]
+    pub body_limit: Option<usize>,
+
+    /// Seconds a request may take before it's answered with 504.
+    #[arg(long, env = "REQUEST_TIMEOUT")]
+    pub request_timeout: Option<u64>,
+
+    /// Seconds to wait for in-flight requests on shutdown.
+    #[arg(long, env = "SHUTDOWN_TIMEOUT")]
+    pub shutdown_timeout: Option<u64>,
+
+    /// Origins allowed by CORS, comma separated.
+    #[arg(long, env = "CORS_ORIGINS", value_delimiter = ',')]
+    pub cors_origins: Vec<String>,
+}
+
+impl ServeArgs {
+    /// Takes the settings of `other` for which `prefer_other` holds.
+    fn merge(&mut self, other: ServeArgs, prefer_other: impl Fn(&str) -> bool) {
+        fn pick<T>(own: &mut T, other: T, prefer_other: bool) {
+            if prefer_other {
+                *own = other;
+            }
+        }
+
+        pick(&mut self.bind, other.bind, prefer_other("bind"));
+        pick(&mut self.storage, other.storage, prefer_other("storage"));
+        pick(&mut self.auto_migrate, other.auto_migrate, prefer_other("auto_migrate"));
+        pick(&mut self.pool_size, other.pool_size, prefer_other("pool_size"));
+        pick(&mut self.connect_retries, other.connect_retries, prefer_other("connect_retries"));
+        pick(&mut self.body_limit, other.body_limit, prefer_other("body_limit"));
+        pick(&mut self.request_timeout, other.request_timeout, prefer_other("request_timeout"));
+        pick(&mut self.shutdown_timeout, other.shutdown_timeout, prefer_other("shutdown_timeout"));
+        pick(&mut self.cors_origins, other.cors_origins, prefer_other("cors_origins"));
+    }
+}
+
+#[derive(Subcommand)]
+pub enum MigrateCommand {
+    /// Apply all pending migrations.
+    Run,
+    /// Revert applied migrations, the last one by default.
+    Revert {
+        /// Revert every applied migration.
+        #[arg(long)]
+        all: bool,
+    },
+    /// List migrations and whether they are applied.
+    Status,
+}
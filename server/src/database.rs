@@ -0,0 +1,110 @@
+// Database backends, picked by the scheme of the database url.
+
+use std::time::Duration;
+
+use diesel_async::{
+    pooled_connection::{AsyncDieselConnectionManager, ManagerConfig},
+    sync_connection_wrapper::SyncConnectionWrapper,
+    AsyncConnection, AsyncPgConnection, SimpleAsyncConnection
+};
+
+use crate::{config::DatabaseConfig, BoxError};
+
+pub type Pool = bb8::Pool<AsyncDieselConnectionManager<AsyncPgConnection>>;
+pub type DbConnection<'a> = bb8::PooledConnection<'a, AsyncDieselConnectionManager<AsyncPgConnection>>;
+
+pub type SqliteConnection = SyncConnectionWrapper<diesel::SqliteConnection>;
+pub type SqlitePool = bb8::Pool<AsyncDieselConnectionManager<SqliteConnection>>;
+pub type SqliteDbConnection<'a> = bb8::PooledConnection<'a, AsyncDieselConnectionManager<SqliteConnection>>;
+
+/// Milliseconds SQLite waits for a lock held by another connection before failing.
+const SQLITE_BUSY_TIMEOUT: u32 = 5000;
+
+/// `sqlite://path` or `sqlite:path` selects SQLite, anything else is a Postgres url.
+pub enum Database {
+    Postgres(String),
+    /// Path of the database file, created when missing.
+    Sqlite(String),
+}
+
+/// Every pooled connection, and the one running migrations, would open its
+/// own empty in-memory or temporary SQLite database.
+#[derive(thiserror::Error, Debug)]
+#[error("SQLite database {0:?} is not kept in a file, use a file path such as sqlite://data.db")]
+pub struct UnsupportedSqlite(String);
+
+impl Database {
+    pub fn from_url(url: &str) -> Result<Self, UnsupportedSqlite> {
+        let Some(path) = url.strip_prefix("sqlite://").or_else(|| url.strip_prefix("sqlite:")) else {
+            return Ok(Self::Postgres(url.to_string()));
+        };
+
+        let in_memory = path.is_empty()
+            || path == ":memory:"
+            || path.starts_with("file::memory:")
+            || path.contains("mode=memory");
+
+        if in_memory {
+            return Err(UnsupportedSqlite(url.to_string()));
+        }
+
+        Ok(Self::Sqlite(path.to_string()))
+    }
+
+    /// Opens and closes a single connection.
+    pub async fn check_connection(&self) -> Result<(), diesel::ConnectionError> {
+        match self {
+            Self::Postgres(url) => AsyncPgConnection::establish(url).await.map(|_| ()),
+            Self::Sqlite(path) => establish_sqlite(path).await.map(|_| ()),
+        }
+    }
+}
+
+diesel::define_sql_function! {
+    /// Unicode aware `lower()`, the built-in one and `LIKE` only fold ASCII.
+    fn unicode_lower(x: diesel::sql_types::Text) -> diesel::sql_types::Text;
+}
+
+/// SQLite leaves foreign keys off and fails on locks right away unless told otherwise,
+/// so every connection gets these settings and [`unicode_lower`].
+pub async fn establish_sqlite(path: &str) -> diesel::ConnectionResult<SqliteConnection> {
+    let mut conn = SqliteConnection::establish(path).await?;
+
+    conn.spawn_blocking(|conn| unicode_lower_utils::register_impl(conn, |x: String| x.to_lowercase()))
+        .await
+        .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
+
+    conn.batch_execute(&format!(
+        "PRAGMA foreign_keys = ON; PRAGMA busy_timeout = {SQLITE_BUSY_TIMEOUT}; PRAGMA journal_mode = WAL;"
+    ))
+        .await
+        .map_err(diesel::ConnectionError::CouldntSetupConfiguration)?;
+
+    Ok(conn)
+}
+
+pub async fn pg_pool(url: &str, config: &DatabaseConfig) -> Result<Pool, BoxError> {
+    build_pool(AsyncDieselConnectionManager::new(url), config).await
+}
+
+pub async fn sqlite_pool(path: &str, config: &DatabaseConfig) -> Result<SqlitePool, BoxError> {
+    let mut manager_config = ManagerConfig::default();
+    manager_config.custom_setup = Box::new(|path| Box::pin(establish_sqlite(path)));
+
+    build_pool(AsyncDieselConnectionManager::new_with_config(path, manager_config), config).await
+}
+
+async fn build_pool<M: bb8::ManageConnection>(manager: M, config: &DatabaseConfig) -> Result<bb8::Pool<M>, BoxError>
+where
+    M::Error: std::error::Error + Send + Sync + 'static,
+{
+    let pool = bb8::Pool::builder()
+        .max_size(config.pool_size)
+        .min_idle(config.min_idle)
+        .connection_timeout(Duration::from_secs(config.connect_timeout))
+        .idle_timeout(config.idle_timeout.map(Duration::from_secs))
+        .build(manager)
+        .await?;
+
+    Ok(pool)
+}
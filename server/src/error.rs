@@ -1,30 +1,157 @@
 use std::error;
 
 use axum::{response::{Response, IntoResponse, Json}, http::StatusCode};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+
+/// Unique constraints and the request fields they guard.
+const UNIQUE_CONSTRAINT_FIELDS: &[(&str, &str)] = &[
+    ("houses_pk", "id"),
+    ("room_pk", "id"),
+    ("device_pk", "id"),
+    ("houses_name_unique", "name"),
+    ("rooms_house_id_name_unique", "name"),
+    ("index_devices_on_room_id_and_name", "name"),
+];
+
+/// SQLite reports the constrained columns instead of the constraint name,
+/// these map back to the names the Postgres schema uses.
+const SQLITE_UNIQUE_COLUMNS: &[(&str, &str)] = &[
+    ("houses.id", "houses_pk"),
+    ("rooms.id", "room_pk"),
+    ("devices.id", "device_pk"),
+    ("houses.name", "houses_name_unique"),
+    ("rooms.house_id, rooms.name", "rooms_house_id_name_unique"),
+    ("devices.room_id, devices.name", "index_devices_on_room_id_and_name"),
+];
+
+/// Prefix of SQLite unique violation messages, followed by the column list.
+const SQLITE_UNIQUE_MESSAGE: &str = "UNIQUE constraint failed: ";
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error(transparent)]
-    Internal(Box<dyn error::Error>),
+    #[error("Internal server error")]
+    Internal(Box<dyn error::Error + Send + Sync>),
     #[error("Not found")]
-    NotFound
+    NotFound,
+    #[error("{0}")]
+    BadRequest(String),
+    #[error("Duplicate {}", field.as_deref().unwrap_or(constraint))]
+    Conflict {
+        constraint: String,
+        field: Option<String>
+    },
+    #[error("Validation failed")]
+    Validation(Vec<shared::FieldError>),
+    #[error("Resource was modified, If-Match does not match")]
+    PreconditionFailed,
+    #[error("Request body is too large")]
+    PayloadTooLarge,
+    #[error("Request timed out")]
+    Timeout
 }
 
 impl Error {
-    pub fn from_internal<T: error::Error + 'static>(error: T) -> Self {
+    pub fn from_internal<T: error::Error + Send + Sync + 'static>(error: T) -> Self {
         Self::Internal(Box::new(error))
     }
+
+    /// Maps unique violations to `Conflict`, everything else is internal.
+    pub fn from_db(error: DieselError) -> Self {
+        match &error {
+            DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, info) => {
+                let constraint = info
+                    .constraint_name()
+                    .or_else(|| {
+                        let columns = info.message().strip_prefix(SQLITE_UNIQUE_MESSAGE)?;
+                        SQLITE_UNIQUE_COLUMNS
+                            .iter()
+                            .find(|(name, _)| *name == columns)
+                            .map(|(_, constraint)| *constraint)
+                    })
+                    .unwrap_or_default();
+
+                Self::conflict(constraint)
+            },
+            _ => Self::from_internal(error)
+        }
+    }
+
+    /// Violation of the named unique constraint.
+    pub fn conflict(constraint: &str) -> Self {
+        let field = UNIQUE_CONSTRAINT_FIELDS
+            .iter()
+            .find(|(name, _)| *name == constraint)
+            .map(|(_, field)| field.to_string());
+
+        Self::Conflict { constraint: constraint.to_string(), field }
+    }
+
+    fn code(&self) -> shared::ErrorCode {
+        match self {
+            Self::Internal(_) => shared::ErrorCode::Internal,
+            Self::NotFound => shared::ErrorCode::NotFound,
+            Self::BadRequest(_) => shared::ErrorCode::BadRequest,
+            Self::Conflict { .. } => shared::ErrorCode::Conflict,
+            Self::Validation(_) => shared::ErrorCode::ValidationFailed,
+            Self::PreconditionFailed => shared::ErrorCode::PreconditionFailed,
+            Self::PayloadTooLarge => shared::ErrorCode::PayloadTooLarge,
+            Self::Timeout => shared::ErrorCode::Timeout
+        }
+    }
+}
+
+/// Error message followed by the messages of all its sources.
+fn error_chain(err: &(dyn error::Error + 'static)) -> String {
+    let mut chain = err.to_string();
+    let mut source = err.source();
+
+    while let Some(err) = source {
+        chain.push_str(": ");
+        chain.push_str(&err.to_string());
+        source = err.source();
+    }
+
+    chain
+}
+
+impl From<DieselError> for Error {
+    fn from(value: DieselError) -> Self {
+        Self::from_db(value)
+    }
 }
 
 impl IntoResponse for Error {
     fn into_response(self) -> Response {
-        let error = shared::Error {
-            error: self.to_string()
+        let mut error = shared::Error {
+            error: self.to_string(),
+            code: self.code(),
+            field: None,
+            errors: Vec::new(),
+            error_id: None
         };
 
+        // Details stay in the log, the client only gets an id to quote.
+        if let Self::Internal(err) = &self {
+            let error_id = uuid::Uuid::new_v4().to_string();
+            tracing::error!(error_id, error = %error_chain(err.as_ref()), "Internal error");
+            error.error_id = Some(error_id);
+        }
+
+        match &self {
+            Self::Conflict { field, .. } => error.field = field.clone(),
+            Self::Validation(errors) => error.errors = errors.clone(),
+            _ => {}
+        }
+
         match self {
             Self::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, Json(error)),
-            Self::NotFound => (StatusCode::NOT_FOUND, Json(error))
+            Self::NotFound => (StatusCode::NOT_FOUND, Json(error)),
+            Self::BadRequest(_) => (StatusCode::BAD_REQUEST, Json(error)),
+            Self::Conflict { .. } => (StatusCode::CONFLICT, Json(error)),
+            Self::Validation(_) => (StatusCode::UNPROCESSABLE_ENTITY, Json(error)),
+            Self::PreconditionFailed => (StatusCode::PRECONDITION_FAILED, Json(error)),
+            Self::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, Json(error)),
+            Self::Timeout => (StatusCode::GATEWAY_TIMEOUT, Json(error))
         }.into_response()
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,84 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{FromRequest, FromRequestParts, Query, Request, rejection::JsonRejection},
+    http::{header, request::Parts, StatusCode},
+    response::Json
+};
+use shared::Validate;
+
+use crate::{error::Error, repository::Precondition};
+
+/// JSON body extractor that also runs `shared::Validate` rules.
+///
+/// Malformed bodies are rejected with 400, invalid ones with 422 and
+/// the list of offending fields, bodies over the limit with 413.
+pub struct ValidJson<T>(pub T);
+
+impl<S, T> FromRequest<S> for ValidJson<T>
+where
+    S: Send + Sync,
+    T: Validate,
+    Json<T>: FromRequest<S, Rejection = JsonRejection>
+{
+    type Rejection = Error;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => Error::PayloadTooLarge,
+                _ => Error::BadRequest(rejection.body_text())
+            })?;
+
+        value.validate().map_err(Error::Validation)?;
+
+        Ok(Self(value))
+    }
+}
+
+/// Query string extractor that also runs `shared::Validate` rules.
+pub struct ValidQuery<T>(pub T);
+
+impl<S, T> FromRequestParts<S> for ValidQuery<T>
+where
+    S: Send + Sync,
+    T: Validate + serde::de::DeserializeOwned
+{
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|rejection| match rejection.status() {
+                StatusCode::PAYLOAD_TOO_LARGE => Error::PayloadTooLarge,
+                _ => Error::BadRequest(rejection.body_text())
+            })?;
+
+        value.validate().map_err(Error::Validation)?;
+
+        Ok(Self(value))
+    }
+}
+
+/// Optional `If-Match` request header.
+pub struct IfMatch(pub Option<String>);
+
+impl From<IfMatch> for Precondition {
+    fn from(if_match: IfMatch) -> Self {
+        if_match.0.map(Precondition::matching).unwrap_or_default()
+    }
+}
+
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let value = parts.headers
+            .get(header::IF_MATCH)
+            // A header that is not valid ASCII can never match
+            .map(|value| value.to_str().unwrap_or_default().to_string());
+
+        Ok(Self(value))
+    }
+}
@@ -0,0 +1,487 @@
+// The house API as a library, so it can be served by `main.rs` or mounted into another app.
+
+mod model;
+mod pagination;
+mod schema;
+
+pub mod cli;
+pub mod config;
+pub mod database;
+pub mod error;
+pub mod extract;
+pub mod migrations;
+pub mod repository;
+pub mod startup;
+pub mod telemetry;
+pub mod transfer;
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    Router,
+    routing,
+    error_handling::HandleErrorLayer,
+    extract::{DefaultBodyLimit, State, Path},
+    middleware,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    response::{IntoResponse, Json, Response}
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use tower::{timeout::{error::Elapsed, TimeoutLayer}, ServiceBuilder};
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::{DefaultOnResponse, TraceLayer}
+};
+
+use config::{Config, CorsConfig};
+use error::Error;
+use extract::{IfMatch, ValidJson, ValidQuery};
+use repository::{Repository, Saved};
+
+pub type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Upper bound for readiness checks, so probes get an answer while the database hangs.
+const READINESS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Request id set on every request and echoed in the response.
+const X_REQUEST_ID: header::HeaderName = header::HeaderName::from_static("x-request-id");
+
+/// JSON response carrying the entity tag of its body.
+type Tagged<T> = (StatusCode, [(header::HeaderName, String); 1], Json<T>);
+
+/// Everything the handlers share: the storage, the configuration of the
+/// HTTP layers and optional features, and the metrics to serve.
+pub struct AppState<R> {
+    repository: R,
+    config: Config,
+    metrics: Option<PrometheusHandle>
+}
+
+impl<R: Repository> AppState<R> {
+    /// State with the default configuration and without `/metrics`.
+    pub fn new(repository: R) -> Self {
+        Self { repository, config: Config::default(), metrics: None }
+    }
+
+    /// Only the `http`, `cors` and `features` sections affect the router.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Serves `/metrics` from the handle, see [`telemetry::install_recorder`].
+    pub fn with_metrics(mut self, metrics: PrometheusHandle) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+}
+
+/// The whole API with its middleware, ready to be served or nested into another app.
+pub fn router<R: Repository>(state: AppState<R>) -> Router {
+    let mut app = Router::new()
+        .route("/healthz", routing::get(healthz))
+        .route("/readyz", routing::get(readyz::<R>))
+        .route("/version", routing::get(version))
+        .route("/houses", routing::get(list_houses::<R>).post(create_house::<R>))
+        .route(
+            "/houses/{id}",
+            routing::get(get_house::<R>)
+                .patch(patch_house::<R>)
+                .put(replace_house::<R>)
+                .delete(delete_house::<R>)
+        )
+        .route("/houses/{house_id}/rooms", routing::get(list_rooms::<R>).post(create_room::<R>))
+        .route(
+            "/rooms/{id}",
+            routing::get(get_room::<R>)
+                .patch(patch_room::<R>)
+                .put(replace_room::<R>)
+                .delete(delete_room::<R>)
+        )
+        .route("/rooms/{room_id}/devices", routing::get(list_devices::<R>).post(create_device::<R>))
+        .route(
+            "/rooms/{room_id}/devices/{device_id}",
+            routing::get(get_device::<R>)
+                .patch(patch_device::<R>)
+                .put(replace_device::<R>)
+                .delete(delete_device::<R>)
+        )
+        .route("/rooms/{room_id}/devices/{device_id}/move", routing::post(move_device::<R>))
+    ;
+
+    if state.config.features.report {
+        app = app.route("/houses/{house_id}/report", routing::get(get_report::<R>));
+    }
+
+    if state.metrics.is_some() {
+        app = app.route("/metrics", routing::get(telemetry::render::<R>));
+    }
+
+    // Layers must come after the routes, they only wrap routes added before them.
+    // The first layer listed is the outermost one.
+    app
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(
+                    TraceLayer::new_for_http()
+                        .make_span_with(telemetry::make_request_span)
+                        .on_response(DefaultOnResponse::new().level(tracing::Level::INFO))
+                )
+                .layer(PropagateRequestIdLayer::x_request_id())
+                .layer(middleware::from_fn(telemetry::track_requests))
+                .option_layer(cors_layer(&state.config.cors))
+                .layer(compression_layer(state.config.http.compression))
+                .layer(HandleErrorLayer::new(handle_middleware_error))
+                .layer(TimeoutLayer::new(Duration::from_secs(state.config.http.request_timeout)))
+                .layer(DefaultBodyLimit::max(state.config.http.body_limit))
+        )
+        .with_state(Arc::new(state))
+}
+
+/// Maps errors of fallible layers, i.e. the request timeout.
+async fn handle_middleware_error(err: BoxError) -> Error {
+    if err.is::<Elapsed>() {
+        Error::Timeout
+    } else {
+        Error::Internal(err)
+    }
+}
+
+/// Disabled compression keeps the layer with no codecs, so the service type stays the same.
+fn compression_layer(enabled: bool) -> CompressionLayer {
+    let layer = CompressionLayer::new();
+
+    if enabled { layer } else { layer.no_gzip().no_br() }
+}
+
+/// Origins were checked by config validation, so invalid ones can't show up here.
+fn cors_layer(config: &CorsConfig) -> Option<CorsLayer> {
+    if config.allowed_origins.is_empty() {
+        return None;
+    }
+
+    let allow_origin = if config.allowed_origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(config.allowed_origins.iter().filter_map(|origin| origin.parse::<HeaderValue>().ok()))
+    };
+
+    let mut layer = CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::PATCH, Method::DELETE])
+        .allow_headers([header::CONTENT_TYPE, header::ACCEPT, header::IF_MATCH, X_REQUEST_ID])
+        .expose_headers([header::ETAG, X_REQUEST_ID]);
+
+    if let Some(max_age) = config.max_age {
+        layer = layer.max_age(Duration::from_secs(max_age));
+    }
+
+    Some(layer)
+}
+
+async fn healthz() -> Json<shared::Health> {
+    Json(shared::Health { status: shared::HealthStatus::Ok, checks: Vec::new() })
+}
+
+async fn readyz<R: Repository>(State(state): State<Arc<AppState<R>>>) -> (StatusCode, Json<shared::Health>) {
+    let checks = tokio::time::timeout(READINESS_TIMEOUT, state.repository.readiness())
+        .await
+        .unwrap_or_else(|_| vec![health_check("database", Ok::<_, Error>(Some("timed out".to_string())))]);
+
+    let ready = checks.iter().all(|check| check.status == shared::HealthStatus::Ok);
+    let (status_code, status) = if ready {
+        (StatusCode::OK, shared::HealthStatus::Ok)
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, shared::HealthStatus::Unavailable)
+    };
+
+    (status_code, Json(shared::Health { status, checks }))
+}
+
+/// `Ok(Some(message))` is a failed check without an underlying error.
+fn health_check<E: std::fmt::Display>(name: &str, result: Result<Option<String>, E>) -> shared::HealthCheck {
+    let (status, message) = match result {
+        Ok(None) => (shared::HealthStatus::Ok, None),
+        Ok(Some(message)) => (shared::HealthStatus::Unavailable, Some(message)),
+        Err(err) => {
+            tracing::warn!("Readiness check {name} failed: {err}");
+            (shared::HealthStatus::Unavailable, Some("check failed".to_string()))
+        }
+    };
+
+    shared::HealthCheck { name: name.to_string(), status, message }
+}
+
+async fn version() -> Json<shared::Version> {
+    Json(shared::Version {
+        name: env!("CARGO_PKG_NAME").to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_hash: option_env!("GIT_HASH").map(ToString::to_string)
+    })
+}
+
+async fn list_houses<R: Repository>(
+    State(state): State<Arc<AppState<R>>>
+) -> Result<(StatusCode, Json<Vec<shared::House>>), Error> {
+    state.repository
+        .list_houses()
+        .await
+        .map(|result| (StatusCode::OK, Json(result)))
+}
+
+async fn create_house<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    ValidJson(new_house): ValidJson<shared::NewHouse>
+) -> Result<(StatusCode, Json<shared::House>), Error> {
+    state.repository
+        .create_house(new_house)
+        .await
+        .map(|result| (StatusCode::CREATED, Json(result)))
+}
+
+async fn get_house<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>
+) -> Result<(StatusCode, Json<shared::House>), Error> {
+    state.repository
+        .get_house(house_id)
+        .await
+        .map(|result| (StatusCode::OK, Json(result)))
+}
+
+async fn patch_house<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+    ValidJson(patch): ValidJson<shared::HousePatch>
+) -> Result<(StatusCode, Json<shared::House>), Error> {
+    state.repository
+        .patch_house(house_id, patch)
+        .await
+        .map(|result| (StatusCode::OK, Json(result)))
+}
+
+/// Replaces the house or creates it at the given id.
+async fn replace_house<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+    ValidJson(new_house): ValidJson<shared::NewHouse>
+) -> Result<(StatusCode, Json<shared::House>), Error> {
+    let (status, house) = saved_status(state.repository.replace_house(house_id, new_house).await?);
+
+    Ok((status, Json(house)))
+}
+
+async fn delete_house<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+) -> Result<StatusCode, Error> {
+    state.repository.delete_house(house_id).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn list_rooms<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+    ValidQuery(list_query): ValidQuery<shared::ListQuery>
+) -> Result<(StatusCode, Json<shared::Page<shared::Room>>), Error> {
+    state.repository
+        .list_rooms(house_id, list_query)
+        .await
+        .map(|page| (StatusCode::OK, Json(page)))
+}
+
+async fn create_room<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+    ValidJson(new_room): ValidJson<shared::NewRoom>
+) -> Result<Tagged<shared::Room>, Error> {
+    state.repository
+        .create_room(house_id, new_room)
+        .await
+        .map(|result| tagged_room(StatusCode::CREATED, result))
+}
+
+async fn get_room<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(room_id): Path<uuid::Uuid>
+) -> Result<Tagged<shared::Room>, Error> {
+    state.repository
+        .get_room(room_id)
+        .await
+        .map(|room| tagged_room(StatusCode::OK, room))
+}
+
+async fn patch_room<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(room_id): Path<uuid::Uuid>,
+    if_match: IfMatch,
+    ValidJson(patch): ValidJson<shared::RoomPatch>
+) -> Result<Tagged<shared::Room>, Error> {
+    state.repository
+        .patch_room(room_id, if_match.into(), patch)
+        .await
+        .map(|result| tagged_room(StatusCode::OK, result))
+}
+
+/// Replaces the room or creates it at the given id.
+async fn replace_room<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(room_id): Path<uuid::Uuid>,
+    if_match: IfMatch,
+    ValidJson(replacement): ValidJson<shared::ReplaceRoom>
+) -> Result<Tagged<shared::Room>, Error> {
+    let (status, room) = saved_status(state.repository.replace_room(room_id, if_match.into(), replacement).await?);
+
+    Ok(tagged_room(status, room))
+}
+
+async fn delete_room<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(room_id): Path<uuid::Uuid>,
+    if_match: IfMatch
+) -> Result<StatusCode, Error> {
+    state.repository.delete_room(room_id, if_match.into()).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn tagged_room(status: StatusCode, room: shared::Room) -> Tagged<shared::Room> {
+    (status, [(header::ETAG, room.etag())], Json(room))
+}
+
+async fn list_devices<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(room_id): Path<uuid::Uuid>,
+    ValidQuery(list_query): ValidQuery<shared::ListQuery>
+) -> Result<(StatusCode, Json<shared::Page<shared::Device>>), Error> {
+    state.repository
+        .list_devices(room_id, list_query)
+        .await
+        .map(|page| (StatusCode::OK, Json(page)))
+}
+
+async fn create_device<R: Repository>(
+    Path(room_id): Path<uuid::Uuid>,
+    State(state): State<Arc<AppState<R>>>,
+    ValidJson(new_device): ValidJson<shared::NewDevice>
+) -> Result<Tagged<shared::Device>, Error> {
+    state.repository
+        .create_device(room_id, new_device)
+        .await
+        .map(|result| tagged_device(StatusCode::CREATED, result))
+}
+
+async fn get_device<R: Repository>(
+    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState<R>>>
+) -> Result<Tagged<shared::Device>, Error> {
+    state.repository
+        .get_device(room_id, device_id)
+        .await
+        .map(|device| tagged_device(StatusCode::OK, device))
+}
+
+async fn patch_device<R: Repository>(
+    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState<R>>>,
+    if_match: IfMatch,
+    ValidJson(patch): ValidJson<shared::DevicePatch>
+) -> Result<Tagged<shared::Device>, Error> {
+    state.repository
+        .patch_device(room_id, device_id, if_match.into(), patch)
+        .await
+        .map(|result| tagged_device(StatusCode::OK, result))
+}
+
+/// Replaces the device or creates it at the given id.
+async fn replace_device<R: Repository>(
+    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState<R>>>,
+    if_match: IfMatch,
+    ValidJson(new_device): ValidJson<shared::NewDevice>
+) -> Result<Tagged<shared::Device>, Error> {
+    let saved = state.repository.replace_device(room_id, device_id, if_match.into(), new_device).await?;
+    let (status, device) = saved_status(saved);
+
+    Ok(tagged_device(status, device))
+}
+
+async fn delete_device<R: Repository>(
+    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState<R>>>,
+    if_match: IfMatch
+) -> Result<StatusCode, Error> {
+    state.repository.delete_device(room_id, device_id, if_match.into()).await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Moves the device to another room keeping its id.
+async fn move_device<R: Repository>(
+    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
+    State(state): State<Arc<AppState<R>>>,
+    ValidJson(target): ValidJson<shared::MoveDevice>
+) -> Result<Tagged<shared::Device>, Error> {
+    state.repository
+        .move_device(room_id, device_id, target.room_id)
+        .await
+        .map(|result| tagged_device(StatusCode::OK, result))
+}
+
+fn tagged_device(status: StatusCode, device: shared::Device) -> Tagged<shared::Device> {
+    (status, [(header::ETAG, device.etag())], Json(device))
+}
+
+/// 201 for entities created by a PUT, 200 for replaced ones.
+fn saved_status<T>(saved: Saved<T>) -> (StatusCode, T) {
+    match saved {
+        Saved::Created(entity) => (StatusCode::CREATED, entity),
+        Saved::Updated(entity) => (StatusCode::OK, entity)
+    }
+}
+
+async fn get_report<R: Repository>(
+    State(state): State<Arc<AppState<R>>>,
+    Path(house_id): Path<uuid::Uuid>,
+    headers: HeaderMap
+) -> Result<Response, Error> {
+    let report = state.repository.report(house_id).await?;
+
+    if prefers_plain_text(&headers) {
+        Ok((StatusCode::OK, report.to_string()).into_response())
+    } else {
+        Ok((StatusCode::OK, Json(report)).into_response())
+    }
+}
+
+/// Picks between JSON and plain text by `Accept` quality values, JSON wins ties.
+fn prefers_plain_text(headers: &HeaderMap) -> bool {
+    let Some(accept) = headers.get(header::ACCEPT).and_then(|value| value.to_str().ok()) else {
+        return false;
+    };
+
+    let mut json_q = 0.0;
+    let mut text_q = 0.0;
+
+    for range in accept.split(',') {
+        let mut params = range.split(';').map(str::trim);
+        let media_type = params.next().unwrap_or_default().to_ascii_lowercase();
+        let q = params
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse::<f32>().ok())
+            .unwrap_or(1.0);
+
+        match media_type.as_str() {
+            "application/json" | "application/*" => json_q = f32::max(json_q, q),
+            "text/plain" | "text/*" => text_q = f32::max(text_q, q),
+            _ => {}
+        }
+    }
+
+    text_q > json_q
+}
+
@@ -1,358 +1,210 @@
-mod error;
-mod model;
-mod schema;
-
-use std::{env, net::SocketAddr, sync::Arc};
-
-use axum::{
-    Router,
-    routing,
-    extract::{State, Path},
-    http::StatusCode,
-    response::Json,
-    serve
-};
-use tower::ServiceBuilder;
-use tower_http::trace::TraceLayer;
+use std::{fs, io::{self, Read}, time::Duration};
+
+use axum::serve;
 use tokio::net;
 use dotenv::dotenv;
-use diesel::prelude::*;
-use diesel_async::{
-    pooled_connection::AsyncDieselConnectionManager, AsyncPgConnection, RunQueryDsl
-};
-
-use error::Error;
-
-use crate::model::*;
 
-type Pool = bb8::Pool<AsyncDieselConnectionManager<AsyncPgConnection>>;
-type DbConnection<'a> = bb8::PooledConnection<'a, AsyncDieselConnectionManager<AsyncPgConnection>>;
-
-struct AppState {
-    pool: Pool
-}
-
-impl AppState {
-    async fn get_db_connection(&self) -> Result<DbConnection<'_>, Error> {
-        self.pool.get().await.map_err(Error::from_internal)
-    }
-}
+use server::{
+    cli::{Cli, Command, MigrateCommand},
+    config::{Config, Storage},
+    database::{self, Database},
+    migrations,
+    repository::{MemoryRepository, PgRepository, Repository, SqliteRepository},
+    startup::{self, StartupError},
+    telemetry,
+    transfer::{Dump, Transfer},
+    AppState, BoxError
+};
 
 #[tokio::main]
 async fn main() {
-    dotenv().unwrap();
-    env_logger::init();
-
-    let pool = establish_db_connection().await;
-    let app_state = Arc::new(AppState { pool });
-
-    let app = Router::new()
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-        )
-        .route("/", routing::get(get_house))
-        .route("/rooms", routing::get(list_rooms).post(create_room))
-        .route(
-            "/rooms/{id}",
-            routing::get(get_room)
-                .patch(update_room)
-                .put(update_room)
-                .delete(delete_room)
-        )
-        .route("/rooms/{room_id}/devices", routing::get(list_devices).post(create_device))
-        .route(
-            "/rooms/{room_id}/devices/{device_id}",
-            routing::get(get_device)
-                .patch(update_device)
-                .put(update_device)
-                .delete(delete_device)
-        )
-        .route("/report", routing::get(get_report))
-        .with_state(app_state)
-    ;
-
-    let addr: SocketAddr = env::var("API_HOST").unwrap().parse().unwrap();
-    log::info!("Starting server in {addr}");
-
-    let listener = net::TcpListener::bind(addr).await.unwrap();
-
-    serve(listener, app).await.unwrap();
-}
+    dotenv().ok();
 
-async fn establish_db_connection() -> Pool {
-    let db_url = std::env::var("DATABASE_URL").unwrap();
-    let config = AsyncDieselConnectionManager::<diesel_async::AsyncPgConnection>::new(db_url);
-    bb8::Pool::builder().build(config).await.unwrap()
-}
+    let cli = Cli::parse_args();
 
-async fn get_house(
-    State(state): State<Arc<AppState>>
-) -> Result<(StatusCode, Json<shared::House>), Error> {
-    let mut conn = state.get_db_connection().await?;
+    let config = match Config::load(&cli) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{err}");
+            std::process::exit(1);
+        }
+    };
 
-    use model::houses::dsl::*;
+    if cli.print_config {
+        print!("{}", config.to_redacted_toml());
+        return;
+    }
 
-    let result = houses
-        .select(House::as_select())
-        .first(&mut conn)
-        .await
-        .optional();
+    telemetry::init_tracing(&config.log);
 
-    match result {
-        Ok(Some(result)) => Ok((StatusCode::OK, Json(result.into()))),
-        Ok(None) => Err(Error::NotFound),
-        Err(err) => Err(Error::from_internal(err))
+    if let Err(err) = run(cli.command, &config).await {
+        tracing::error!("{err}");
+        std::process::exit(1);
     }
 }
 
-async fn list_rooms(
-    State(state): State<Arc<AppState>>
-) -> Result<(StatusCode, Json<Vec<shared::Room>>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::rooms::dsl::*;
-
-    rooms
-        .select(Room::as_select())
-        .load(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| {
-            let result: Vec<shared::Room> = result.into_iter().map(Into::into).collect();
-            (StatusCode::OK, Json(result))
-        })
+async fn run(command: Option<Command>, config: &Config) -> Result<(), BoxError> {
+    // Serving from memory works without a database, every other command needs one.
+    let database_url = || config.database_url.clone().ok_or(StartupError::MissingDatabaseUrl);
+
+    match command.unwrap_or(Command::Serve(Default::default())) {
+        Command::Serve(_) => Ok(serve_api(config).await?),
+        Command::Migrate(MigrateCommand::Run) => {
+            let versions = migrations::run(&database_url()?).await?;
+            if versions.is_empty() {
+                println!("No pending migrations");
+            }
+            for version in versions {
+                println!("Applied {version}");
+            }
+            Ok(())
+        },
+        Command::Migrate(MigrateCommand::Revert { all }) => {
+            for version in migrations::revert(&database_url()?, all).await? {
+                println!("Reverted {version}");
+            }
+            Ok(())
+        },
+        Command::Migrate(MigrateCommand::Status) => {
+            for (name, applied) in migrations::status(&database_url()?).await? {
+                println!("[{}] {name}", if applied { "X" } else { " " });
+            }
+            Ok(())
+        },
+        command @ (Command::Seed | Command::Export { .. } | Command::Import { .. }) => {
+            match Database::from_url(&database_url()?)? {
+                Database::Postgres(url) => {
+                    let pool = database::pg_pool(&url, &config.database).await?;
+                    transfer_command(command, PgRepository::new(pool)).await
+                },
+                Database::Sqlite(path) => {
+                    let pool = database::sqlite_pool(&path, &config.database).await?;
+                    transfer_command(command, SqliteRepository::new(pool)).await
+                }
+            }
+        },
+    }
 }
 
-async fn create_room(
-    State(state): State<Arc<AppState>>,
-    Json(new_room): Json<shared::NewRoom>
-) -> Result<(StatusCode, Json<shared::Room>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    let new_room: NewRoom = new_room.into();
+async fn transfer_command(command: Command, repository: impl Transfer) -> Result<(), BoxError> {
+    match command {
+        Command::Seed => {
+            if repository.demo_exists().await? {
+                println!("Demo house already exists");
+            } else {
+                let (houses, rooms, devices) = repository.import(Dump::demo()).await?;
+                println!("Created {houses} house(s), {rooms} room(s), {devices} device(s)");
+            }
+        },
+        Command::Export { house_ids, output } => {
+            let dump = repository.export(&house_ids).await?;
+
+            match output {
+                Some(path) => serde_json::to_writer_pretty(fs::File::create(path)?, &dump)?,
+                None => serde_json::to_writer_pretty(io::stdout().lock(), &dump)?,
+            }
+        },
+        Command::Import { input } => {
+            let json = match input {
+                Some(path) => fs::read_to_string(path)?,
+                None => {
+                    let mut json = String::new();
+                    io::stdin().read_to_string(&mut json)?;
+                    json
+                }
+            };
+
+            let (houses, rooms, devices) = repository.import(serde_json::from_str(&json)?).await?;
+            println!("Created {houses} house(s), {rooms} room(s), {devices} device(s)");
+        },
+        Command::Serve(_) | Command::Migrate(_) => unreachable!("not a transfer command"),
+    }
 
-    diesel::insert_into(rooms::table)
-        .values(new_room)
-        .returning(Room::as_returning())
-        .get_result(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| (StatusCode::CREATED, Json(result.into())))
+    Ok(())
 }
 
-async fn get_room(
-    State(state): State<Arc<AppState>>,
-    Path(room_id): Path<uuid::Uuid>
-) -> Result<(StatusCode, Json<shared::Room>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::rooms::dsl::*;
-
-    let result = rooms
-        .find(room_id)
-        .select(Room::as_select())
-        .first(&mut conn)
-        .await
-        .optional();
-
-    match result {
-        Ok(Some(room)) => Ok((StatusCode::OK, Json(room.into()))),
-        Ok(None) => Err(Error::NotFound),
-        Err(err) => Err(Error::from_internal(err))
+async fn serve_api(config: &Config) -> Result<(), StartupError> {
+    if config.storage == Storage::Memory {
+        tracing::warn!("Using in-memory storage, nothing is kept across restarts");
+        return serve_with(config, MemoryRepository::new()).await;
     }
-}
 
-async fn update_room(
-    State(state): State<Arc<AppState>>,
-    Path(room_id): Path<uuid::Uuid>,
-    Json(new_room): Json<shared::NewRoom>
-) -> Result<(StatusCode, Json<shared::Room>), Error> {
-    let mut conn = state.get_db_connection().await?;
+    let database_url = config.database_url.as_deref().ok_or(StartupError::MissingDatabaseUrl)?;
 
-    use model::rooms::dsl::*;
+    let database = Database::from_url(database_url)?;
 
-    let room = rooms.find(room_id);
+    startup::wait_for_database(&database, config.database.connect_retries).await?;
+    ensure_schema_is_current(database_url, config.features.auto_migrate).await?;
 
-    room.select(id)
-        .first::<uuid::Uuid>(&mut conn)
-        .await
-        .optional()
-        .map_err(Error::from_internal)?
-        .ok_or(Error::NotFound)?
-    ;
-
-    diesel::update(room)
-        .set(name.eq(new_room.name))
-        .returning(Room::as_returning())
-        .get_result(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| (StatusCode::OK, Json(result.into())))
+    // The repository owns the pool, its connections close once the router
+    // and any request still running after the drain deadline let go of it.
+    match database {
+        Database::Postgres(url) => {
+            let pool = database::pg_pool(&url, &config.database).await.map_err(StartupError::Pool)?;
+            serve_with(config, PgRepository::new(pool)).await
+        },
+        Database::Sqlite(path) => {
+            let pool = database::sqlite_pool(&path, &config.database).await.map_err(StartupError::Pool)?;
+            serve_with(config, SqliteRepository::new(pool)).await
+        }
+    }
 }
 
-async fn delete_room(
-    State(state): State<Arc<AppState>>,
-    Path(room_id): Path<uuid::Uuid>,
-) -> Result<StatusCode, Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::rooms::dsl::*;
+async fn serve_with<R: Repository>(config: &Config, repository: R) -> Result<(), StartupError> {
+    let addr = config.bind;
 
-    let room = rooms.find(room_id);
-
-    room.select(id)
-        .first::<uuid::Uuid>(&mut conn)
-        .await
-        .optional()
-        .map_err(Error::from_internal)?
-        .ok_or(Error::NotFound)?
-    ;
-
-    diesel::delete(room)
-        .execute(&mut conn)
+    let metrics = telemetry::install_recorder().map_err(StartupError::Metrics)?;
+    let app = server::router(AppState::new(repository).with_config(config.clone()).with_metrics(metrics));
+    let listener = net::TcpListener::bind(addr)
         .await
-        .map_err(Error::from_internal)?;
-
-    Ok(StatusCode::NO_CONTENT)
-}
+        .map_err(|source| StartupError::Bind { addr, source })?;
 
-async fn list_devices(
-    State(state): State<Arc<AppState>>,
-    Path(room_id): Path<uuid::Uuid>
-) -> Result<(StatusCode, Json<Vec<shared::Device>>), Error> {
-    let mut conn = state.get_db_connection().await?;
+    tracing::info!("Starting server in {addr}");
 
-    use model::devices::dsl;
+    // Flips once a shutdown signal arrives, starting the drain timeout.
+    let (shutdown_tx, mut shutdown_rx) = tokio::sync::watch::channel(false);
 
-    dsl::devices
-        .filter(dsl::room_id.eq(room_id))
-        .select(Device::as_select())
-        .load(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| {
-            let result: Vec<shared::Device> = result.into_iter().map(Into::into).collect();
-            (StatusCode::OK, Json(result))
+    let server = serve(listener, app)
+        .with_graceful_shutdown(async move {
+            startup::shutdown_signal().await;
+            tracing::info!("Shutting down, waiting for in-flight requests");
+            shutdown_tx.send_replace(true);
         })
-}
-
-async fn create_device(
-    Path(room_id): Path<uuid::Uuid>,
-    State(state): State<Arc<AppState>>,
-    Json(new_device): Json<shared::NewDevice>
-) -> Result<(StatusCode, Json<shared::Device>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    let new_device = NewDevice {
-        room_id,
-        name: new_device.name
+        .into_future();
+
+    let drain_timeout = Duration::from_secs(config.http.shutdown_timeout);
+    let drain_deadline = async move {
+        if shutdown_rx.wait_for(|&shutdown| shutdown).await.is_ok() {
+            tokio::time::sleep(drain_timeout).await;
+        } else {
+            std::future::pending::<()>().await;
+        }
     };
 
-    use model::rooms::dsl as rooms_dsl;
-
-    let room = rooms_dsl::rooms.find(room_id);
-
-    room.select(rooms_dsl::id)
-        .first::<uuid::Uuid>(&mut conn)
-        .await
-        .optional()
-        .map_err(Error::from_internal)?
-        .ok_or(Error::NotFound)?
-    ;
-
-    diesel::insert_into(devices::table)
-        .values(new_device)
-        .returning(Device::as_returning())
-        .get_result(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| (StatusCode::CREATED, Json(result.into())))
-}
-
-async fn get_device(
-    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
-    State(state): State<Arc<AppState>>
-) -> Result<(StatusCode, Json<shared::Device>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::devices::dsl;
-
-    let result = dsl::devices
-        .filter(dsl::room_id.eq(room_id))
-        .filter(dsl::id.eq(device_id))
-        .select(Device::as_select())
-        .first(&mut conn)
-        .await
-        .optional();
-
-    match result {
-        Ok(Some(device)) => Ok((StatusCode::OK, Json(device.into()))),
-        Ok(None) => Err(Error::NotFound),
-        Err(err) => Err(Error::from_internal(err))
+    tokio::select! {
+        result = server => result.map_err(StartupError::Serve)?,
+        _ = drain_deadline => tracing::warn!("Requests still in flight after {drain_timeout:?}, dropping them"),
     }
-}
 
-async fn update_device(
-    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
-    State(state): State<Arc<AppState>>,
-    Json(new_device): Json<shared::NewDevice>
-) -> Result<(StatusCode, Json<shared::Device>), Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::devices::dsl;
-
-    let device = dsl::devices
-        .filter(dsl::room_id.eq(room_id))
-        .filter(dsl::id.eq(device_id))
-    ;
-
-    device.select(dsl::id)
-        .first::<uuid::Uuid>(&mut conn)
-        .await
-        .optional()
-        .map_err(Error::from_internal)?
-        .ok_or(Error::NotFound)?
-    ;
-
-    diesel::update(device)
-        .set(dsl::name.eq(new_device.name))
-        .returning(Device::as_returning())
-        .get_result(&mut conn)
-        .await
-        .map_err(Error::from_internal)
-        .map(|result| (StatusCode::OK, Json(result.into())))
+    Ok(())
 }
 
-async fn delete_device(
-    Path((room_id, device_id)): Path<(uuid::Uuid, uuid::Uuid)>,
-    State(state): State<Arc<AppState>>,
-) -> Result<StatusCode, Error> {
-    let mut conn = state.get_db_connection().await?;
-
-    use model::devices::dsl;
+/// Serving on an outdated schema fails every request, so startup stops here instead.
+async fn ensure_schema_is_current(database_url: &str, auto_migrate: bool) -> Result<(), StartupError> {
+    if auto_migrate {
+        for version in migrations::run(database_url).await.map_err(StartupError::Migrations)? {
+            tracing::info!("Applied migration {version}");
+        }
 
-    let device = dsl::devices
-        .filter(dsl::room_id.eq(room_id))
-        .filter(dsl::id.eq(device_id));
-
-    device.select(dsl::id)
-        .first::<uuid::Uuid>(&mut conn)
-        .await
-        .optional()
-        .map_err(Error::from_internal)?
-        .ok_or(Error::NotFound)?
-    ;
+        return Ok(());
+    }
 
-    diesel::delete(device)
-        .execute(&mut conn)
-        .await
-        .map_err(Error::from_internal)?;
+    let pending = migrations::pending(database_url).await.map_err(StartupError::Migrations)?;
 
-    Ok(StatusCode::NO_CONTENT)
-}
+    if !pending.is_empty() {
+        return Err(StartupError::SchemaBehind(pending));
+    }
 
-async fn get_report() -> String {
-    "get_report".to_string()
+    Ok(())
 }
 
@@ -0,0 +1,159 @@
+use diesel::{
+    backend::Backend, migration::MigrationSource, pg::Pg, sql_types::Text, sqlite::Sqlite, Connection,
+    QueryableByName, SqliteConnection
+};
+use diesel_async::{async_connection_wrapper::AsyncConnectionWrapper, AsyncPgConnection, RunQueryDsl};
+use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+
+use crate::{database::{self, Database}, BoxError};
+
+pub const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
+/// The Postgres migrations rely on `pgcrypto` and plpgsql triggers, SQLite has a set of its own.
+pub const SQLITE_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations_sqlite");
+
+/// Connection with the migration harness of its backend.
+enum HarnessConnection {
+    Postgres(AsyncConnectionWrapper<AsyncPgConnection>),
+    Sqlite(SqliteConnection),
+}
+
+/// Runs `$body` with `$conn` bound to the backend connection and
+/// `$migrations` to the migrations of that backend.
+macro_rules! with_migrations {
+    ($harness:expr, |$conn:ident, $migrations:ident| $body:expr) => {
+        match $harness {
+            HarnessConnection::Postgres($conn) => {
+                let $migrations = MIGRATIONS;
+                $body
+            },
+            HarnessConnection::Sqlite($conn) => {
+                let $migrations = SQLITE_MIGRATIONS;
+                $body
+            }
+        }
+    };
+}
+
+/// Migration harness runs synchronously, so it gets a blocking thread
+/// with a sync or wrapped async connection.
+async fn with_harness<T, F>(database_url: &str, f: F) -> Result<T, BoxError>
+where
+    T: Send + 'static,
+    F: FnOnce(&mut HarnessConnection) -> Result<T, BoxError> + Send + 'static,
+{
+    let database = Database::from_url(database_url)?;
+
+    tokio::task::spawn_blocking(move || {
+        let mut conn = match database {
+            Database::Postgres(url) => HarnessConnection::Postgres(AsyncConnectionWrapper::establish(&url)?),
+            Database::Sqlite(path) => HarnessConnection::Sqlite(SqliteConnection::establish(&path)?),
+        };
+        f(&mut conn)
+    })
+        .await?
+}
+
+/// Applies pending migrations, returns versions of the applied ones.
+pub async fn run(database_url: &str) -> Result<Vec<String>, BoxError> {
+    with_harness(database_url, |harness| {
+        with_migrations!(harness, |conn, migrations| {
+            let versions = conn.run_pending_migrations(migrations)?;
+            Ok(versions.iter().map(ToString::to_string).collect())
+        })
+    })
+        .await
+}
+
+/// Lists names of migrations that are not applied yet.
+pub async fn pending(database_url: &str) -> Result<Vec<String>, BoxError> {
+    with_harness(database_url, |harness| {
+        with_migrations!(harness, |conn, migrations| {
+            let migrations = conn.pending_migrations(migrations)?;
+            Ok(migrations.iter().map(|migration| migration.name().to_string()).collect())
+        })
+    })
+        .await
+}
+
+#[derive(QueryableByName)]
+struct AppliedMigration {
+    #[diesel(sql_type = Text)]
+    version: String,
+}
+
+const APPLIED_MIGRATIONS_QUERY: &str = "SELECT version FROM __diesel_schema_migrations";
+
+/// Same as [`pending`] over an existing async connection, for periodic checks
+/// that shouldn't open a connection of their own.
+pub async fn pending_on(conn: &mut AsyncPgConnection) -> Result<Vec<String>, BoxError> {
+    let applied = diesel::sql_query(APPLIED_MIGRATIONS_QUERY)
+        .load::<AppliedMigration>(conn)
+        .await?;
+
+    unapplied::<Pg>(&MIGRATIONS, &applied)
+}
+
+/// [`pending_on`] for SQLite.
+pub async fn pending_on_sqlite(conn: &mut database::SqliteConnection) -> Result<Vec<String>, BoxError> {
+    let applied = diesel::sql_query(APPLIED_MIGRATIONS_QUERY)
+        .load::<AppliedMigration>(conn)
+        .await?;
+
+    unapplied::<Sqlite>(&SQLITE_MIGRATIONS, &applied)
+}
+
+fn unapplied<DB: Backend>(migrations: &EmbeddedMigrations, applied: &[AppliedMigration]) -> Result<Vec<String>, BoxError> {
+    let migrations = MigrationSource::<DB>::migrations(migrations)?;
+
+    Ok(
+        migrations
+            .iter()
+            .map(|migration| migration.name())
+            .filter(|name| applied.iter().all(|applied| applied.version != name.version().to_string()))
+            .map(ToString::to_string)
+            .collect()
+    )
+}
+
+/// Reverts the last applied migration or all of them, returns versions of the reverted ones.
+pub async fn revert(database_url: &str, all: bool) -> Result<Vec<String>, BoxError> {
+    with_harness(database_url, move |harness| {
+        with_migrations!(harness, |conn, migrations| {
+            if all {
+                let versions = conn.revert_all_migrations(migrations)?;
+                Ok(versions.iter().map(ToString::to_string).collect())
+            } else {
+                let version = conn.revert_last_migration(migrations)?;
+                Ok(vec![version.to_string()])
+            }
+        })
+    })
+        .await
+}
+
+/// Lists embedded migrations with a flag telling whether each one is applied.
+pub async fn status(database_url: &str) -> Result<Vec<(String, bool)>, BoxError> {
+    with_harness(database_url, |harness| {
+        with_migrations!(harness, |conn, migrations| status_on(conn, migrations))
+    })
+        .await
+}
+
+fn status_on<DB: Backend>(
+    conn: &mut impl MigrationHarness<DB>,
+    migrations: EmbeddedMigrations
+) -> Result<Vec<(String, bool)>, BoxError> {
+    let applied = conn.applied_migrations()?;
+    let migrations = MigrationSource::<DB>::migrations(&migrations)?;
+
+    Ok(
+        migrations
+            .iter()
+            .map(|migration| {
+                let name = migration.name();
+                (name.to_string(), applied.contains(&name.version()))
+            })
+            .collect()
+    )
+}
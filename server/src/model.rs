@@ -1,5 +1,6 @@
-// use std::time;
+// Timestamp columns hold UTC: diesel-async sets the session time zone to UTC.
 
+use chrono::NaiveDateTime;
 use diesel::prelude::*;
 
 pub use super::schema::*;
@@ -8,11 +9,32 @@ pub use super::schema::*;
 #[diesel(table_name = houses)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct House {
+    pub id: uuid::Uuid,
     pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 impl From<House> for shared::House {
     fn from(value: House) -> Self {
+        Self {
+            id: value.id,
+            name: value.name,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = houses)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NewHouse {
+    pub name: String,
+}
+
+impl From<shared::NewHouse> for NewHouse {
+    fn from(value: shared::NewHouse) -> Self {
         Self { name: value.name }
     }
 }
@@ -22,12 +44,21 @@ impl From<House> for shared::House {
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct Room {
     pub id: uuid::Uuid,
+    pub house_id: uuid::Uuid,
     pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 impl From<Room> for shared::Room {
     fn from(value: Room) -> Self {
-        Self { id: value.id, name: value.name }
+        Self {
+            id: value.id,
+            house_id: value.house_id,
+            name: value.name,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
     }
 }
 
@@ -35,15 +66,10 @@ impl From<Room> for shared::Room {
 #[diesel(table_name = rooms)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct NewRoom {
+    pub house_id: uuid::Uuid,
     pub name: String,
 }
 
-impl From<shared::NewRoom> for NewRoom {
-    fn from(value: shared::NewRoom) -> Self {
-        Self { name: value.name }
-    }
-}
-
 #[derive(Queryable, Selectable)]
 #[diesel(table_name = devices)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -51,11 +77,25 @@ pub struct Device {
     pub id: uuid::Uuid,
     pub room_id: uuid::Uuid,
     pub name: String,
+    #[diesel(
+        select_expression = (devices::kind, devices::is_on, devices::power, devices::temperature),
+        deserialize_as = DeviceKindColumns
+    )]
+    pub kind: shared::DeviceKind,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
 }
 
 impl From<Device> for shared::Device {
     fn from(value: Device) -> Self {
-        Self { id: value.id, room_id: value.room_id, name: value.name }
+        Self {
+            id: value.id,
+            room_id: value.room_id,
+            name: value.name,
+            kind: value.kind,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
     }
 }
 
@@ -65,6 +105,54 @@ impl From<Device> for shared::Device {
 pub struct NewDevice {
     pub room_id: uuid::Uuid,
     pub name: String,
+    #[diesel(embed)]
+    pub kind: DeviceKindColumns,
+}
+
+/// Flat column representation of `shared::DeviceKind`.
+///
+/// Properties of other kinds are written as NULL, so switching the kind
+/// of a device clears the stale ones.
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = devices)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct DeviceKindColumns {
+    pub kind: String,
+    pub is_on: Option<bool>,
+    pub power: Option<f64>,
+    pub temperature: Option<f64>,
+}
+
+impl From<shared::DeviceKind> for DeviceKindColumns {
+    fn from(value: shared::DeviceKind) -> Self {
+        let kind = value.name().to_string();
+
+        match value {
+            shared::DeviceKind::Socket { is_on, power } => Self {
+                kind, is_on: Some(is_on), power: Some(power), temperature: None
+            },
+            shared::DeviceKind::Thermometer { temperature } => Self {
+                kind, is_on: None, power: None, temperature: Some(temperature)
+            }
+        }
+    }
+}
+
+impl TryFrom<DeviceKindColumns> for shared::DeviceKind {
+    type Error = String;
+
+    fn try_from(value: DeviceKindColumns) -> Result<Self, Self::Error> {
+        match value {
+            DeviceKindColumns { kind, is_on: Some(is_on), power: Some(power), .. } if kind == "socket" => {
+                Ok(Self::Socket { is_on, power })
+            },
+            DeviceKindColumns { kind, temperature: Some(temperature), .. } if kind == "thermometer" => {
+                Ok(Self::Thermometer { temperature })
+            },
+            DeviceKindColumns { kind, .. } => Err(format!("Invalid properties for device kind {kind:?}"))
+        }
+    }
 }
 
 
@@ -0,0 +1,81 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, NaiveDateTime};
+use serde::{Serialize, Deserialize};
+use shared::SortBy;
+
+use crate::error::Error;
+
+/// Keyset pagination position: sort key and id of the last entry of a page.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Cursor {
+    /// `created_at` in microseconds since the epoch
+    CreatedAt(i64, uuid::Uuid),
+    Name(String, uuid::Uuid)
+}
+
+impl Cursor {
+    pub fn new(sort: SortBy, created_at: NaiveDateTime, name: &str, id: uuid::Uuid) -> Self {
+        match sort {
+            SortBy::CreatedAt => Self::CreatedAt(created_at.and_utc().timestamp_micros(), id),
+            SortBy::Name => Self::Name(name.to_string(), id)
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        let json = serde_json::to_vec(self).expect("cursor is always serializable");
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    /// Decodes a cursor, which must have been issued for the same `sort`.
+    pub fn decode(value: &str, sort: SortBy) -> Result<Self, Error> {
+        let invalid = || Error::BadRequest("Invalid cursor".to_string());
+
+        let json = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+        let cursor: Self = serde_json::from_slice(&json).map_err(|_| invalid())?;
+
+        match (&cursor, sort) {
+            (Self::CreatedAt(..), SortBy::CreatedAt) | (Self::Name(..), SortBy::Name) => Ok(cursor),
+            _ => Err(invalid())
+        }
+    }
+
+    pub fn created_at(micros: i64) -> Result<NaiveDateTime, Error> {
+        DateTime::from_timestamp_micros(micros)
+            .map(|value| value.naive_utc())
+            .ok_or_else(|| Error::BadRequest("Invalid cursor".to_string()))
+    }
+}
+
+/// LIKE pattern matching `value` as a literal substring.
+pub fn contains_pattern(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+
+    format!("%{escaped}%")
+}
+
+/// Builds a page from up to `limit + 1` loaded rows, the extra row only
+/// signals that there is a next page.
+pub fn into_page<T, U>(
+    mut rows: Vec<T>,
+    limit: u32,
+    cursor: impl Fn(&T) -> Cursor
+) -> shared::Page<U>
+where
+    T: Into<U>
+{
+    let next_cursor = if rows.len() > limit as usize {
+        rows.truncate(limit as usize);
+        rows.last().map(|last| cursor(last).encode())
+    } else {
+        None
+    };
+
+    shared::Page {
+        items: rows.into_iter().map(Into::into).collect(),
+        next_cursor
+    }
+}
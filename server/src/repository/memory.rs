@@ -0,0 +1,552 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+
+use super::{missing_house, missing_room, now, Counts, Precondition, Repository, Saved};
+use crate::{error::Error, health_check, pagination::{self, Cursor}};
+
+/// Repository keeping everything in process memory, for demos and tests.
+/// Constraints of the database schema are enforced by hand.
+#[derive(Default)]
+pub struct MemoryRepository {
+    store: RwLock<Store>
+}
+
+#[derive(Default)]
+struct Store {
+    houses: HashMap<uuid::Uuid, shared::House>,
+    rooms: HashMap<uuid::Uuid, shared::Room>,
+    devices: HashMap<uuid::Uuid, shared::Device>,
+}
+
+impl MemoryRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Store {
+    fn house_name_taken(&self, name: &str, except: uuid::Uuid) -> bool {
+        self.houses.values().any(|house| house.name == name && house.id != except)
+    }
+
+    fn room_name_taken(&self, house_id: uuid::Uuid, name: &str, except: uuid::Uuid) -> bool {
+        self.rooms.values().any(|room| room.house_id == house_id && room.name == name && room.id != except)
+    }
+
+    fn device_name_taken(&self, room_id: uuid::Uuid, name: &str, except: uuid::Uuid) -> bool {
+        self.devices.values().any(|device| device.room_id == room_id && device.name == name && device.id != except)
+    }
+
+    fn device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid) -> Result<&shared::Device, Error> {
+        self.devices
+            .get(&device_id)
+            .filter(|device| device.room_id == room_id)
+            .ok_or(Error::NotFound)
+    }
+
+    fn device_mut(&mut self, room_id: uuid::Uuid, device_id: uuid::Uuid) -> Result<&mut shared::Device, Error> {
+        self.devices
+            .get_mut(&device_id)
+            .filter(|device| device.room_id == room_id)
+            .ok_or(Error::NotFound)
+    }
+
+    fn delete_room(&mut self, room_id: uuid::Uuid) {
+        self.rooms.remove(&room_id);
+        self.devices.retain(|_, device| device.room_id != room_id);
+    }
+}
+
+impl Repository for MemoryRepository {
+    async fn list_houses(&self) -> Result<Vec<shared::House>, Error> {
+        let store = self.store.read().await;
+
+        let mut houses: Vec<_> = store.houses.values().cloned().collect();
+        houses.sort_by_key(|house| (house.created_at, house.id));
+
+        Ok(houses)
+    }
+
+    async fn create_house(&self, new_house: shared::NewHouse) -> Result<shared::House, Error> {
+        let mut store = self.store.write().await;
+
+        let now = now();
+        let house = shared::House { id: uuid::Uuid::new_v4(), name: new_house.name, created_at: now, updated_at: now };
+
+        if store.house_name_taken(&house.name, house.id) {
+            return Err(Error::conflict("houses_name_unique"));
+        }
+
+        store.houses.insert(house.id, house.clone());
+
+        Ok(house)
+    }
+
+    async fn get_house(&self, house_id: uuid::Uuid) -> Result<shared::House, Error> {
+        self.store.read().await.houses.get(&house_id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn patch_house(&self, house_id: uuid::Uuid, patch: shared::HousePatch) -> Result<shared::House, Error> {
+        let mut store = self.store.write().await;
+
+        if !store.houses.contains_key(&house_id) {
+            return Err(Error::NotFound);
+        }
+
+        if let Some(name) = patch.name {
+            if store.house_name_taken(&name, house_id) {
+                return Err(Error::conflict("houses_name_unique"));
+            }
+
+            let house = store.houses.get_mut(&house_id).ok_or(Error::NotFound)?;
+            touch(&mut house.name, name, &mut house.updated_at);
+        }
+
+        store.houses.get(&house_id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn replace_house(
+        &self,
+        house_id: uuid::Uuid,
+        new_house: shared::NewHouse
+    ) -> Result<Saved<shared::House>, Error> {
+        let mut store = self.store.write().await;
+
+        if store.house_name_taken(&new_house.name, house_id) {
+            return Err(Error::conflict("houses_name_unique"));
+        }
+
+        if let Some(house) = store.houses.get_mut(&house_id) {
+            touch(&mut house.name, new_house.name, &mut house.updated_at);
+            return Ok(Saved::Updated(house.clone()));
+        }
+
+        let now = now();
+        let house = shared::House { id: house_id, name: new_house.name, created_at: now, updated_at: now };
+        store.houses.insert(house_id, house.clone());
+
+        Ok(Saved::Created(house))
+    }
+
+    async fn delete_house(&self, house_id: uuid::Uuid) -> Result<(), Error> {
+        let mut store = self.store.write().await;
+
+        store.houses.remove(&house_id).ok_or(Error::NotFound)?;
+
+        let room_ids: Vec<_> = store.rooms.values().filter(|room| room.house_id == house_id).map(|room| room.id).collect();
+        for room_id in room_ids {
+            store.delete_room(room_id);
+        }
+
+        Ok(())
+    }
+
+    async fn list_rooms(
+        &self,
+        house_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Room>, Error> {
+        let store = self.store.read().await;
+
+        if !store.houses.contains_key(&house_id) {
+            return Err(Error::NotFound);
+        }
+
+        page(store.rooms.values().filter(|room| room.house_id == house_id), list_query)
+    }
+
+    async fn create_room(&self, house_id: uuid::Uuid, new_room: shared::NewRoom) -> Result<shared::Room, Error> {
+        let mut store = self.store.write().await;
+
+        if !store.houses.contains_key(&house_id) {
+            return Err(Error::NotFound);
+        }
+
+        let now = now();
+        let room = shared::Room { id: uuid::Uuid::new_v4(), house_id, name: new_room.name, created_at: now, updated_at: now };
+
+        if store.room_name_taken(house_id, &room.name, room.id) {
+            return Err(Error::conflict("rooms_house_id_name_unique"));
+        }
+
+        store.rooms.insert(room.id, room.clone());
+
+        Ok(room)
+    }
+
+    async fn get_room(&self, room_id: uuid::Uuid) -> Result<shared::Room, Error> {
+        self.store.read().await.rooms.get(&room_id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn patch_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::RoomPatch
+    ) -> Result<shared::Room, Error> {
+        let mut store = self.store.write().await;
+
+        let current = store.rooms.get(&room_id).ok_or(Error::NotFound)?;
+
+        precondition.check(&current.etag())?;
+
+        if let Some(name) = patch.name {
+            if store.room_name_taken(current.house_id, &name, room_id) {
+                return Err(Error::conflict("rooms_house_id_name_unique"));
+            }
+
+            let room = store.rooms.get_mut(&room_id).ok_or(Error::NotFound)?;
+            touch(&mut room.name, name, &mut room.updated_at);
+        }
+
+        store.rooms.get(&room_id).cloned().ok_or(Error::NotFound)
+    }
+
+    async fn replace_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        replacement: shared::ReplaceRoom
+    ) -> Result<Saved<shared::Room>, Error> {
+        let mut store = self.store.write().await;
+
+        match store.rooms.get(&room_id) {
+            Some(current) => precondition.check(&current.etag())?,
+            None if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+            None => {}
+        }
+
+        if !store.houses.contains_key(&replacement.house_id) {
+            return Err(missing_house());
+        }
+
+        if store.room_name_taken(replacement.house_id, &replacement.name, room_id) {
+            return Err(Error::conflict("rooms_house_id_name_unique"));
+        }
+
+        if let Some(room) = store.rooms.get_mut(&room_id) {
+            let changed = room.house_id != replacement.house_id || room.name != replacement.name;
+
+            room.house_id = replacement.house_id;
+            room.name = replacement.name;
+            if changed {
+                room.updated_at = now();
+            }
+
+            return Ok(Saved::Updated(room.clone()));
+        }
+
+        let now = now();
+        let room = shared::Room {
+            id: room_id,
+            house_id: replacement.house_id,
+            name: replacement.name,
+            created_at: now,
+            updated_at: now
+        };
+        store.rooms.insert(room_id, room.clone());
+
+        Ok(Saved::Created(room))
+    }
+
+    async fn delete_room(&self, room_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut store = self.store.write().await;
+
+        let current = store.rooms.get(&room_id).ok_or(Error::NotFound)?;
+
+        precondition.check(&current.etag())?;
+
+        store.delete_room(room_id);
+
+        Ok(())
+    }
+
+    async fn list_devices(
+        &self,
+        room_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Device>, Error> {
+        let store = self.store.read().await;
+
+        if !store.rooms.contains_key(&room_id) {
+            return Err(Error::NotFound);
+        }
+
+        page(store.devices.values().filter(|device| device.room_id == room_id), list_query)
+    }
+
+    async fn create_device(
+        &self,
+        room_id: uuid::Uuid,
+        new_device: shared::NewDevice
+    ) -> Result<shared::Device, Error> {
+        let mut store = self.store.write().await;
+
+        if !store.rooms.contains_key(&room_id) {
+            return Err(Error::NotFound);
+        }
+
+        let now = now();
+        let device = shared::Device {
+            id: uuid::Uuid::new_v4(),
+            room_id,
+            name: new_device.name,
+            kind: new_device.kind,
+            created_at: now,
+            updated_at: now
+        };
+
+        if store.device_name_taken(room_id, &device.name, device.id) {
+            return Err(Error::conflict("index_devices_on_room_id_and_name"));
+        }
+
+        store.devices.insert(device.id, device.clone());
+
+        Ok(device)
+    }
+
+    async fn get_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid) -> Result<shared::Device, Error> {
+        self.store.read().await.device(room_id, device_id).cloned()
+    }
+
+    async fn patch_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::DevicePatch
+    ) -> Result<shared::Device, Error> {
+        let mut store = self.store.write().await;
+
+        let current = store.device(room_id, device_id)?;
+
+        precondition.check(&current.etag())?;
+
+        let kind = patch.apply_to_kind(&current.kind).map_err(Error::Validation)?;
+        let name = patch.name.unwrap_or_else(|| current.name.clone());
+
+        if store.device_name_taken(room_id, &name, device_id) {
+            return Err(Error::conflict("index_devices_on_room_id_and_name"));
+        }
+
+        let device = store.device_mut(room_id, device_id)?;
+        if device.name != name || device.kind != kind {
+            device.name = name;
+            device.kind = kind;
+            device.updated_at = now();
+        }
+
+        Ok(device.clone())
+    }
+
+    async fn replace_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        new_device: shared::NewDevice
+    ) -> Result<Saved<shared::Device>, Error> {
+        let mut store = self.store.write().await;
+
+        let exists = match store.device(room_id, device_id) {
+            Ok(current) => {
+                precondition.check(&current.etag())?;
+                true
+            },
+            Err(_) if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+            Err(_) => false
+        };
+
+        if !exists && !store.rooms.contains_key(&room_id) {
+            return Err(Error::NotFound);
+        }
+
+        if store.device_name_taken(room_id, &new_device.name, device_id) {
+            return Err(Error::conflict("index_devices_on_room_id_and_name"));
+        }
+
+        if exists {
+            let device = store.device_mut(room_id, device_id)?;
+            if device.name != new_device.name || device.kind != new_device.kind {
+                device.name = new_device.name;
+                device.kind = new_device.kind;
+                device.updated_at = now();
+            }
+
+            return Ok(Saved::Updated(device.clone()));
+        }
+
+        // The id may belong to a device of another room
+        if store.devices.contains_key(&device_id) {
+            return Err(Error::conflict("device_pk"));
+        }
+
+        let now = now();
+        let device = shared::Device {
+            id: device_id,
+            room_id,
+            name: new_device.name,
+            kind: new_device.kind,
+            created_at: now,
+            updated_at: now
+        };
+        store.devices.insert(device_id, device.clone());
+
+        Ok(Saved::Created(device))
+    }
+
+    async fn delete_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut store = self.store.write().await;
+
+        let current = store.device(room_id, device_id)?;
+
+        precondition.check(&current.etag())?;
+
+        store.devices.remove(&device_id);
+
+        Ok(())
+    }
+
+    async fn move_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        target_room_id: uuid::Uuid
+    ) -> Result<shared::Device, Error> {
+        let mut store = self.store.write().await;
+
+        let current = store.device(room_id, device_id)?;
+
+        if target_room_id == room_id {
+            return Ok(current.clone());
+        }
+
+        if !store.rooms.contains_key(&target_room_id) {
+            return Err(missing_room());
+        }
+
+        if store.device_name_taken(target_room_id, &current.name, device_id) {
+            return Err(Error::conflict("index_devices_on_room_id_and_name"));
+        }
+
+        let device = store.device_mut(room_id, device_id)?;
+        device.room_id = target_room_id;
+        device.updated_at = now();
+
+        Ok(device.clone())
+    }
+
+    async fn report(&self, house_id: uuid::Uuid) -> Result<shared::Report, Error> {
+        let store = self.store.read().await;
+
+        let house = store.houses.get(&house_id).ok_or(Error::NotFound)?;
+
+        let mut rooms: Vec<_> = store.rooms.values().filter(|room| room.house_id == house_id).collect();
+        rooms.sort_by(|a, b| (&a.name, a.id).cmp(&(&b.name, b.id)));
+
+        let report_rooms: Vec<shared::RoomReport> = rooms
+            .into_iter()
+            .map(|room| {
+                let mut devices: Vec<_> = store.devices.values().filter(|device| device.room_id == room.id).cloned().collect();
+                devices.sort_by(|a, b| a.name.cmp(&b.name));
+
+                shared::RoomReport { id: room.id, name: room.name.clone(), devices }
+            })
+            .collect();
+
+        Ok(shared::Report {
+            house_id: house.id,
+            house: house.name.clone(),
+            total_rooms: report_rooms.len(),
+            total_devices: report_rooms.iter().map(|room| room.devices.len()).sum(),
+            rooms: report_rooms,
+        })
+    }
+
+    async fn counts(&self) -> Result<Counts, Error> {
+        let store = self.store.read().await;
+
+        let mut devices = HashMap::new();
+        for device in store.devices.values() {
+            *devices.entry(device.kind.name().to_string()).or_default() += 1;
+        }
+
+        Ok(Counts { houses: store.houses.len() as u64, rooms: store.rooms.len() as u64, devices })
+    }
+
+    async fn readiness(&self) -> Vec<shared::HealthCheck> {
+        vec![health_check("storage", Ok::<_, Error>(None))]
+    }
+}
+
+/// Sets the name, bumping `updated_at` only on an actual change like the database trigger does.
+fn touch(name: &mut String, new_name: String, updated_at: &mut DateTime<Utc>) {
+    if *name != new_name {
+        *name = new_name;
+        *updated_at = now();
+    }
+}
+
+/// Fields that listings filter, sort and paginate by.
+trait Listed: Clone {
+    fn id(&self) -> uuid::Uuid;
+    fn name(&self) -> &str;
+    fn created_at(&self) -> DateTime<Utc>;
+    fn updated_at(&self) -> DateTime<Utc>;
+}
+
+impl Listed for shared::Room {
+    fn id(&self) -> uuid::Uuid { self.id }
+    fn name(&self) -> &str { &self.name }
+    fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+impl Listed for shared::Device {
+    fn id(&self) -> uuid::Uuid { self.id }
+    fn name(&self) -> &str { &self.name }
+    fn created_at(&self) -> DateTime<Utc> { self.created_at }
+    fn updated_at(&self) -> DateTime<Utc> { self.updated_at }
+}
+
+/// Same filters and keyset pagination as the SQL listings, applied in memory.
+fn page<'a, T: Listed + 'a>(
+    items: impl Iterator<Item = &'a T>,
+    list_query: shared::ListQuery
+) -> Result<shared::Page<T>, Error> {
+    let sort = list_query.sort.unwrap_or_default();
+    let limit = list_query.limit.unwrap_or(shared::DEFAULT_PAGE_LIMIT);
+    let name = list_query.name.map(|name| name.to_lowercase());
+
+    let cursor = list_query.cursor.as_deref().map(|cursor| Cursor::decode(cursor, sort)).transpose()?;
+
+    if let Some(Cursor::CreatedAt(micros, _)) = &cursor {
+        Cursor::created_at(*micros)?;
+    }
+
+    let mut items: Vec<T> = items
+        .filter(|item| list_query.updated_since.is_none_or(|since| item.updated_at() >= since))
+        .filter(|item| name.as_ref().is_none_or(|name| item.name().to_lowercase().contains(name)))
+        .filter(|item| match &cursor {
+            Some(Cursor::CreatedAt(micros, last_id)) => {
+                (item.created_at().timestamp_micros(), item.id()) > (*micros, *last_id)
+            },
+            Some(Cursor::Name(last_name, last_id)) => (item.name(), item.id()) > (last_name.as_str(), *last_id),
+            None => true
+        })
+        .cloned()
+        .collect();
+
+    match sort {
+        shared::SortBy::CreatedAt => items.sort_by_key(|item| (item.created_at(), item.id())),
+        shared::SortBy::Name => items.sort_by(|a, b| (a.name(), a.id()).cmp(&(b.name(), b.id())))
+    }
+
+    items.truncate(limit as usize + 1);
+
+    Ok(pagination::into_page(items, limit, |item: &T| {
+        Cursor::new(sort, item.created_at().naive_utc(), item.name(), item.id())
+    }))
+}
@@ -0,0 +1,212 @@
+// Storage behind the HTTP handlers, so they don't depend on a particular database.
+
+mod memory;
+mod postgres;
+mod sqlite;
+
+use std::{collections::HashMap, future::Future};
+
+use chrono::{DateTime, Utc};
+
+pub use memory::MemoryRepository;
+pub use postgres::PgRepository;
+pub use sqlite::SqliteRepository;
+
+use crate::error::Error;
+
+/// Result of a PUT, which replaces an entity or creates it at the given id.
+pub enum Saved<T> {
+    Created(T),
+    Updated(T),
+}
+
+/// Entity tags a conditional write expects the stored entity to have,
+/// e.g. from an `If-Match` header. The default is an unconditional write.
+#[derive(Debug, Clone, Default)]
+pub struct Precondition(Option<String>);
+
+impl Precondition {
+    /// Comma separated tags, `*` matches any existing entity.
+    pub fn matching(tags: impl Into<String>) -> Self {
+        Self(Some(tags.into()))
+    }
+
+    /// Whether the write requires the entity to exist.
+    pub fn is_conditional(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Fails with `Error::PreconditionFailed` unless the write is
+    /// unconditional or `etag` is among the expected tags.
+    pub fn check(&self, etag: &str) -> Result<(), Error> {
+        let Some(tags) = &self.0 else {
+            return Ok(());
+        };
+
+        let matches = tags
+            .split(',')
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag == etag);
+
+        if matches {
+            Ok(())
+        } else {
+            Err(Error::PreconditionFailed)
+        }
+    }
+}
+
+/// Number of stored entities, exported as metrics.
+#[derive(Default)]
+pub struct Counts {
+    pub houses: u64,
+    pub rooms: u64,
+    /// Devices by `DeviceKind::name`.
+    pub devices: HashMap<String, u64>,
+}
+
+/// Houses, rooms and devices with the API semantics: missing entities are
+/// `Error::NotFound`, unique violations `Error::Conflict`, and a
+/// [`Precondition`] is checked atomically with the change it guards.
+pub trait Repository: Send + Sync + 'static {
+    fn list_houses(&self) -> impl Future<Output = Result<Vec<shared::House>, Error>> + Send;
+
+    fn create_house(&self, new_house: shared::NewHouse) -> impl Future<Output = Result<shared::House, Error>> + Send;
+
+    fn get_house(&self, house_id: uuid::Uuid) -> impl Future<Output = Result<shared::House, Error>> + Send;
+
+    fn patch_house(
+        &self,
+        house_id: uuid::Uuid,
+        patch: shared::HousePatch
+    ) -> impl Future<Output = Result<shared::House, Error>> + Send;
+
+    fn replace_house(
+        &self,
+        house_id: uuid::Uuid,
+        new_house: shared::NewHouse
+    ) -> impl Future<Output = Result<Saved<shared::House>, Error>> + Send;
+
+    /// Deletes the house with its rooms and their devices.
+    fn delete_house(&self, house_id: uuid::Uuid) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn list_rooms(
+        &self,
+        house_id: uuid::Uuid,
+        query: shared::ListQuery
+    ) -> impl Future<Output = Result<shared::Page<shared::Room>, Error>> + Send;
+
+    fn create_room(
+        &self,
+        house_id: uuid::Uuid,
+        new_room: shared::NewRoom
+    ) -> impl Future<Output = Result<shared::Room, Error>> + Send;
+
+    fn get_room(&self, room_id: uuid::Uuid) -> impl Future<Output = Result<shared::Room, Error>> + Send;
+
+    fn patch_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::RoomPatch
+    ) -> impl Future<Output = Result<shared::Room, Error>> + Send;
+
+    /// A missing target house is a validation error of `house_id`.
+    fn replace_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        replacement: shared::ReplaceRoom
+    ) -> impl Future<Output = Result<Saved<shared::Room>, Error>> + Send;
+
+    /// Deletes the room with its devices.
+    fn delete_room(&self, room_id: uuid::Uuid, precondition: Precondition) -> impl Future<Output = Result<(), Error>> + Send;
+
+    fn list_devices(
+        &self,
+        room_id: uuid::Uuid,
+        query: shared::ListQuery
+    ) -> impl Future<Output = Result<shared::Page<shared::Device>, Error>> + Send;
+
+    fn create_device(
+        &self,
+        room_id: uuid::Uuid,
+        new_device: shared::NewDevice
+    ) -> impl Future<Output = Result<shared::Device, Error>> + Send;
+
+    fn get_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid
+    ) -> impl Future<Output = Result<shared::Device, Error>> + Send;
+
+    fn patch_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::DevicePatch
+    ) -> impl Future<Output = Result<shared::Device, Error>> + Send;
+
+    fn replace_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        new_device: shared::NewDevice
+    ) -> impl Future<Output = Result<Saved<shared::Device>, Error>> + Send;
+
+    fn delete_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Moves the device to another room keeping its id, a missing target
+    /// room is a validation error of `room_id`.
+    fn move_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        target_room_id: uuid::Uuid
+    ) -> impl Future<Output = Result<shared::Device, Error>> + Send;
+
+    /// Rooms ordered by name, each with its devices ordered by name.
+    fn report(&self, house_id: uuid::Uuid) -> impl Future<Output = Result<shared::Report, Error>> + Send;
+
+    fn counts(&self) -> impl Future<Output = Result<Counts, Error>> + Send;
+
+    /// Checks served by `/readyz`.
+    fn readiness(&self) -> impl Future<Output = Vec<shared::HealthCheck>> + Send;
+
+    /// Records storage specific metrics, such as connection pool state.
+    fn record_metrics(&self) {}
+}
+
+fn missing_house() -> Error {
+    Error::Validation(vec![shared::FieldError::new("house_id", "house does not exist")])
+}
+
+fn missing_room() -> Error {
+    Error::Validation(vec![shared::FieldError::new("room_id", "room does not exist")])
+}
+
+/// Current time at the microsecond precision of Postgres timestamps, which
+/// entity tags and cursors rely on. Used where the application sets timestamps.
+fn now() -> DateTime<Utc> {
+    let now = Utc::now();
+    DateTime::from_timestamp_micros(now.timestamp_micros()).unwrap_or(now)
+}
+
+fn record_pool_metrics<M: bb8::ManageConnection>(pool: &bb8::Pool<M>) {
+    let state = pool.state();
+    let statistics = &state.statistics;
+
+    metrics::gauge!("db_pool_connections").set(state.connections);
+    metrics::gauge!("db_pool_idle_connections").set(state.idle_connections);
+    metrics::counter!("db_pool_gets_total", "result" => "direct").absolute(statistics.get_direct);
+    metrics::counter!("db_pool_gets_total", "result" => "waited").absolute(statistics.get_waited);
+    metrics::counter!("db_pool_gets_total", "result" => "timed_out").absolute(statistics.get_timed_out);
+    metrics::gauge!("db_pool_wait_seconds").set(statistics.get_wait_time.as_secs_f64());
+}
@@ -0,0 +1,890 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel::{dsl::count_star, prelude::*};
+use diesel_async::{scoped_futures::ScopedFutureExt, AsyncConnection, AsyncPgConnection, RunQueryDsl};
+
+use super::{missing_house, missing_room, record_pool_metrics, Counts, Precondition, Repository, Saved};
+use crate::{
+    database::{DbConnection, Pool}, error::Error, health_check, migrations, model::{self, *},
+    pagination::{self, Cursor}, transfer::{Dump, Transfer, DEMO_HOUSE_NAME}, BoxError
+};
+
+/// Repository over a Postgres connection pool.
+#[derive(Clone)]
+pub struct PgRepository {
+    pool: Pool
+}
+
+impl PgRepository {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_db_connection(&self) -> Result<DbConnection<'_>, Error> {
+        self.pool.get().await.map_err(Error::from_internal)
+    }
+}
+
+impl Repository for PgRepository {
+    async fn list_houses(&self) -> Result<Vec<shared::House>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        houses
+            .select(House::as_select())
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| result.into_iter().map(Into::into).collect())
+    }
+
+    async fn create_house(&self, new_house: shared::NewHouse) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let new_house: NewHouse = new_house.into();
+
+        diesel::insert_into(houses::table)
+            .values(new_house)
+            .returning(House::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_house(&self, house_id: uuid::Uuid) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        houses
+            .find(house_id)
+            .select(House::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_house(&self, house_id: uuid::Uuid, patch: shared::HousePatch) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        let house = houses.find(house_id);
+
+        let current = house
+            .select(House::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)?
+        ;
+
+        let Some(new_name) = patch.name else {
+            return Ok(current.into());
+        };
+
+        diesel::update(house)
+            .set(name.eq(new_name))
+            .returning(House::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn replace_house(
+        &self,
+        house_id: uuid::Uuid,
+        new_house: shared::NewHouse
+    ) -> Result<Saved<shared::House>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let exists = houses
+                .find(house_id)
+                .select(id)
+                .for_update()
+                .first::<uuid::Uuid>(conn)
+                .await
+                .optional()?
+                .is_some();
+
+            if exists {
+                diesel::update(houses.find(house_id))
+                    .set(name.eq(new_house.name))
+                    .returning(House::as_returning())
+                    .get_result(conn)
+                    .await
+                    .map_err(Error::from_db)
+                    .map(|result| Saved::Updated(result.into()))
+            } else {
+                diesel::insert_into(houses)
+                    .values((id.eq(house_id), NewHouse::from(new_house)))
+                    .returning(House::as_returning())
+                    .get_result(conn)
+                    .await
+                    .map_err(Error::from_db)
+                    .map(|result| Saved::Created(result.into()))
+            }
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_house(&self, house_id: uuid::Uuid) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        let house = houses.find(house_id);
+
+        house.select(id)
+            .first::<uuid::Uuid>(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)?
+        ;
+
+        diesel::delete(house)
+            .execute(&mut conn)
+            .await
+            .map_err(Error::from_db)?;
+
+        Ok(())
+    }
+
+    async fn list_rooms(
+        &self,
+        house_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Room>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_house_exists(&mut conn, house_id).await?;
+
+        use model::rooms::dsl;
+
+        let sort = list_query.sort.unwrap_or_default();
+        let limit = list_query.limit.unwrap_or(shared::DEFAULT_PAGE_LIMIT);
+
+        let mut query = dsl::rooms
+            .filter(dsl::house_id.eq(house_id))
+            .select(Room::as_select())
+            .limit(i64::from(limit) + 1)
+            .into_boxed();
+
+        if let Some(updated_since) = list_query.updated_since {
+            query = query.filter(dsl::updated_at.ge(updated_since.naive_utc()));
+        }
+
+        // ILIKE folds non-ASCII letters only in databases with a UTF-8 LC_CTYPE
+        if let Some(name) = &list_query.name {
+            query = query.filter(dsl::name.ilike(pagination::contains_pattern(name)));
+        }
+
+        let cursor = list_query.cursor.as_deref().map(|cursor| Cursor::decode(cursor, sort)).transpose()?;
+
+        query = match cursor {
+            Some(Cursor::CreatedAt(micros, last_id)) => {
+                let created_at = Cursor::created_at(micros)?;
+                query.filter(dsl::created_at.gt(created_at).or(dsl::created_at.eq(created_at).and(dsl::id.gt(last_id))))
+            },
+            Some(Cursor::Name(last_name, last_id)) => {
+                query.filter(dsl::name.gt(last_name.clone()).or(dsl::name.eq(last_name).and(dsl::id.gt(last_id))))
+            },
+            None => query
+        };
+
+        query = match sort {
+            shared::SortBy::CreatedAt => query.order((dsl::created_at, dsl::id)),
+            shared::SortBy::Name => query.order((dsl::name, dsl::id))
+        };
+
+        query
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| {
+                pagination::into_page(result, limit, |room: &Room| {
+                    Cursor::new(sort, room.created_at, &room.name, room.id)
+                })
+            })
+    }
+
+    async fn create_room(&self, house_id: uuid::Uuid, new_room: shared::NewRoom) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_house_exists(&mut conn, house_id).await?;
+
+        let new_room = NewRoom {
+            house_id,
+            name: new_room.name
+        };
+
+        diesel::insert_into(rooms::table)
+            .values(new_room)
+            .returning(Room::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_room(&self, room_id: uuid::Uuid) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        rooms
+            .find(room_id)
+            .select(Room::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::RoomPatch
+    ) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(room_id)
+                .select(Room::as_select())
+                .for_update()
+                .first(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.updated_at.and_utc()))?;
+
+            let Some(new_name) = patch.name else {
+                return Ok(current);
+            };
+
+            diesel::update(rooms.find(room_id))
+                .set(name.eq(new_name))
+                .returning(Room::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn replace_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        replacement: shared::ReplaceRoom
+    ) -> Result<Saved<shared::Room>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(room_id)
+                .select(updated_at)
+                .for_update()
+                .first::<NaiveDateTime>(conn)
+                .await
+                .optional()?
+            ;
+
+            let new_room = NewRoom {
+                house_id: replacement.house_id,
+                name: replacement.name
+            };
+
+            match current {
+                Some(current) => precondition.check(&shared::etag(current.and_utc()))?,
+                None if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+                None => {}
+            }
+
+            ensure_house_exists(conn, new_room.house_id)
+                .await
+                .map_err(|err| match err {
+                    Error::NotFound => missing_house(),
+                    err => err
+                })?;
+
+            if current.is_some() {
+                return diesel::update(rooms.find(room_id))
+                    .set((house_id.eq(new_room.house_id), name.eq(new_room.name)))
+                    .returning(Room::as_returning())
+                    .get_result(conn)
+                    .await
+                    .map_err(Error::from_db)
+                    .map(|result| Saved::Updated(result.into()));
+            }
+
+            diesel::insert_into(rooms)
+                .values((id.eq(room_id), new_room))
+                .returning(Room::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+                .map(|result| Saved::Created(result.into()))
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_room(&self, room_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(room_id)
+                .select(updated_at)
+                .for_update()
+                .first::<NaiveDateTime>(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.and_utc()))?;
+
+            diesel::delete(rooms.find(room_id))
+                .execute(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_devices(
+        &self,
+        room_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Device>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_room_exists(&mut conn, room_id).await?;
+
+        use model::devices::dsl;
+
+        let sort = list_query.sort.unwrap_or_default();
+        let limit = list_query.limit.unwrap_or(shared::DEFAULT_PAGE_LIMIT);
+
+        let mut query = dsl::devices
+            .filter(dsl::room_id.eq(room_id))
+            .select(Device::as_select())
+            .limit(i64::from(limit) + 1)
+            .into_boxed();
+
+        if let Some(updated_since) = list_query.updated_since {
+            query = query.filter(dsl::updated_at.ge(updated_since.naive_utc()));
+        }
+
+        if let Some(name) = &list_query.name {
+            query = query.filter(dsl::name.ilike(pagination::contains_pattern(name)));
+        }
+
+        let cursor = list_query.cursor.as_deref().map(|cursor| Cursor::decode(cursor, sort)).transpose()?;
+
+        query = match cursor {
+            Some(Cursor::CreatedAt(micros, last_id)) => {
+                let created_at = Cursor::created_at(micros)?;
+                query.filter(dsl::created_at.gt(created_at).or(dsl::created_at.eq(created_at).and(dsl::id.gt(last_id))))
+            },
+            Some(Cursor::Name(last_name, last_id)) => {
+                query.filter(dsl::name.gt(last_name.clone()).or(dsl::name.eq(last_name).and(dsl::id.gt(last_id))))
+            },
+            None => query
+        };
+
+        query = match sort {
+            shared::SortBy::CreatedAt => query.order((dsl::created_at, dsl::id)),
+            shared::SortBy::Name => query.order((dsl::name, dsl::id))
+        };
+
+        query
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| {
+                pagination::into_page(result, limit, |device: &Device| {
+                    Cursor::new(sort, device.created_at, &device.name, device.id)
+                })
+            })
+    }
+
+    async fn create_device(
+        &self,
+        room_id: uuid::Uuid,
+        new_device: shared::NewDevice
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let new_device = NewDevice {
+            room_id,
+            name: new_device.name,
+            kind: new_device.kind.into()
+        };
+
+        use model::rooms::dsl as rooms_dsl;
+
+        let room = rooms_dsl::rooms.find(room_id);
+
+        room.select(rooms_dsl::id)
+            .first::<uuid::Uuid>(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)?
+        ;
+
+        diesel::insert_into(devices::table)
+            .values(new_device)
+            .returning(Device::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+
+        dsl::devices
+            .filter(dsl::room_id.eq(room_id))
+            .filter(dsl::id.eq(device_id))
+            .select(Device::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::DevicePatch
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let device = dsl::devices
+                .filter(dsl::room_id.eq(room_id))
+                .filter(dsl::id.eq(device_id))
+            ;
+
+            let current = device
+                .select(Device::as_select())
+                .for_update()
+                .first(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.updated_at.and_utc()))?;
+
+            let kind = patch.apply_to_kind(&current.kind).map_err(Error::Validation)?;
+
+            diesel::update(device)
+                .set((
+                    dsl::name.eq(patch.name.unwrap_or(current.name)),
+                    DeviceKindColumns::from(kind)
+                ))
+                .returning(Device::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn replace_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        new_device: shared::NewDevice
+    ) -> Result<Saved<shared::Device>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let device = dsl::devices
+                .filter(dsl::room_id.eq(room_id))
+                .filter(dsl::id.eq(device_id))
+            ;
+
+            let current = device
+                .select(dsl::updated_at)
+                .for_update()
+                .first::<NaiveDateTime>(conn)
+                .await
+                .optional()?
+            ;
+
+            let new_device = NewDevice {
+                room_id,
+                name: new_device.name,
+                kind: new_device.kind.into()
+            };
+
+            match current {
+                Some(current) => precondition.check(&shared::etag(current.and_utc()))?,
+                None if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+                None => {}
+            }
+
+            if current.is_some() {
+                return diesel::update(device)
+                    .set((dsl::name.eq(new_device.name), new_device.kind))
+                    .returning(Device::as_returning())
+                    .get_result(conn)
+                    .await
+                    .map_err(Error::from_db)
+                    .map(|result| Saved::Updated(result.into()));
+            }
+
+            use model::rooms::dsl as rooms_dsl;
+
+            rooms_dsl::rooms
+                .find(room_id)
+                .select(rooms_dsl::id)
+                .first::<uuid::Uuid>(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            diesel::insert_into(dsl::devices)
+                .values((dsl::id.eq(device_id), new_device))
+                .returning(Device::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+                .map(|result| Saved::Created(result.into()))
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let device = dsl::devices
+                .filter(dsl::room_id.eq(room_id))
+                .filter(dsl::id.eq(device_id));
+
+            let current = device
+                .select(dsl::updated_at)
+                .for_update()
+                .first::<NaiveDateTime>(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.and_utc()))?;
+
+            diesel::delete(device)
+                .execute(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn move_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        target_room_id: uuid::Uuid
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+        use model::rooms::dsl as rooms_dsl;
+
+        conn.transaction::<_, Error, _>(|conn| async move {
+            let device = dsl::devices
+                .filter(dsl::room_id.eq(room_id))
+                .filter(dsl::id.eq(device_id))
+            ;
+
+            let current = device
+                .select(Device::as_select())
+                .for_update()
+                .first(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            if target_room_id == room_id {
+                return Ok(current);
+            }
+
+            rooms_dsl::rooms
+                .find(target_room_id)
+                .select(rooms_dsl::id)
+                .for_share()
+                .first::<uuid::Uuid>(conn)
+                .await
+                .optional()?
+                .ok_or_else(missing_room)?
+            ;
+
+            diesel::update(device)
+                .set(dsl::room_id.eq(target_room_id))
+                .returning(Device::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn report(&self, house_id: uuid::Uuid) -> Result<shared::Report, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let house = houses::table
+            .find(house_id)
+            .select(House::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)?
+        ;
+
+        let rows = rooms::table
+            .filter(rooms::house_id.eq(house_id))
+            .left_join(devices::table)
+            .select((Room::as_select(), Option::<Device>::as_select()))
+            .order((rooms::name, rooms::id, devices::name))
+            .load::<(Room, Option<Device>)>(&mut conn)
+            .await
+            .map_err(Error::from_db)?;
+
+        let mut report_rooms: Vec<shared::RoomReport> = Vec::new();
+
+        for (room, device) in rows {
+            if report_rooms.last().is_none_or(|last| last.id != room.id) {
+                report_rooms.push(shared::RoomReport { id: room.id, name: room.name, devices: Vec::new() });
+            }
+
+            if let (Some(device), Some(last)) = (device, report_rooms.last_mut()) {
+                last.devices.push(device.into());
+            }
+        }
+
+        Ok(shared::Report {
+            house_id: house.id,
+            house: house.name,
+            total_rooms: report_rooms.len(),
+            total_devices: report_rooms.iter().map(|room| room.devices.len()).sum(),
+            rooms: report_rooms,
+        })
+    }
+
+    async fn counts(&self) -> Result<Counts, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let total_houses = houses::table.count().get_result::<i64>(&mut conn).await?;
+        let total_rooms = rooms::table.count().get_result::<i64>(&mut conn).await?;
+        let devices_by_kind: HashMap<String, u64> = devices::table
+            .group_by(devices::kind)
+            .select((devices::kind, count_star()))
+            .load::<(String, i64)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|(kind, count)| (kind, count as u64))
+            .collect();
+
+        Ok(Counts { houses: total_houses as u64, rooms: total_rooms as u64, devices: devices_by_kind })
+    }
+
+    /// Ready when a pooled connection answers queries and the schema is current.
+    async fn readiness(&self) -> Vec<shared::HealthCheck> {
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(err) => return vec![health_check("database", Err::<Option<String>, _>(err))]
+        };
+
+        let database = diesel::sql_query("SELECT 1").execute(&mut conn).await.map(|_| None);
+        let migrations = migrations::pending_on(&mut conn).await.map(|pending| {
+            (!pending.is_empty()).then(|| format!("{} pending migration(s)", pending.len()))
+        });
+
+        vec![health_check("database", database), health_check("migrations", migrations)]
+    }
+
+    fn record_metrics(&self) {
+        record_pool_metrics(&self.pool);
+    }
+}
+
+async fn ensure_house_exists(conn: &mut AsyncPgConnection, house_id: uuid::Uuid) -> Result<(), Error> {
+    use model::houses::dsl;
+
+    dsl::houses
+        .find(house_id)
+        .select(dsl::id)
+        .first::<uuid::Uuid>(conn)
+        .await
+        .optional()
+        .map_err(Error::from_db)?
+        .ok_or(Error::NotFound)
+        .map(|_| ())
+}
+
+async fn ensure_room_exists(conn: &mut AsyncPgConnection, room_id: uuid::Uuid) -> Result<(), Error> {
+    use model::rooms::dsl;
+
+    dsl::rooms
+        .find(room_id)
+        .select(dsl::id)
+        .first::<uuid::Uuid>(conn)
+        .await
+        .optional()
+        .map_err(Error::from_db)?
+        .ok_or(Error::NotFound)
+        .map(|_| ())
+}
+
+impl Transfer for PgRepository {
+    async fn export(&self, house_ids: &[uuid::Uuid]) -> Result<Dump, BoxError> {
+        let mut conn = self.pool.get().await?;
+
+        let mut query = houses::table
+            .select(House::as_select())
+            .order((houses::created_at, houses::id))
+            .into_boxed();
+
+        if !house_ids.is_empty() {
+            query = query.filter(houses::id.eq_any(house_ids));
+        }
+
+        let house_rows = query.load(&mut conn).await?;
+
+        let room_rows = rooms::table
+            .filter(rooms::house_id.eq_any(house_rows.iter().map(|house| house.id)))
+            .select(Room::as_select())
+            .order((rooms::created_at, rooms::id))
+            .load(&mut conn)
+            .await?;
+
+        let device_rows = devices::table
+            .filter(devices::room_id.eq_any(room_rows.iter().map(|room| room.id)))
+            .select(Device::as_select())
+            .order((devices::created_at, devices::id))
+            .load(&mut conn)
+            .await?;
+
+        Dump::from_rows(
+            house_ids,
+            house_rows.into_iter().map(Into::into).collect(),
+            room_rows.into_iter().map(Into::into).collect(),
+            device_rows.into_iter().map(Into::into).collect()
+        )
+    }
+
+    async fn import(&self, dump: Dump) -> Result<(usize, usize, usize), BoxError> {
+        dump.validate()?;
+
+        let mut conn = self.pool.get().await?;
+
+        let counts = conn.transaction::<_, diesel::result::Error, _>(|conn| async move {
+            let (mut total_rooms, mut total_devices) = (0, 0);
+
+            for house in &dump.houses {
+                let house_id = diesel::insert_into(houses::table)
+                    .values((house.id.map(|value| houses::id.eq(value)), houses::name.eq(&house.name)))
+                    .returning(houses::id)
+                    .get_result::<uuid::Uuid>(conn)
+                    .await?;
+
+                for room in &house.rooms {
+                    let room_id = diesel::insert_into(rooms::table)
+                        .values((
+                            room.id.map(|value| rooms::id.eq(value)),
+                            NewRoom { house_id, name: room.name.clone() }
+                        ))
+                        .returning(rooms::id)
+                        .get_result::<uuid::Uuid>(conn)
+                        .await?;
+
+                    for device in &room.devices {
+                        diesel::insert_into(devices::table)
+                            .values((
+                                device.id.map(|value| devices::id.eq(value)),
+                                NewDevice { room_id, name: device.name.clone(), kind: device.kind.clone().into() }
+                            ))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    total_devices += room.devices.len();
+                }
+
+                total_rooms += house.rooms.len();
+            }
+
+            Ok((dump.houses.len(), total_rooms, total_devices))
+        }.scope_boxed())
+            .await?;
+
+        Ok(counts)
+    }
+
+    async fn demo_exists(&self) -> Result<bool, BoxError> {
+        let mut conn = self.pool.get().await?;
+
+        let count = houses::table
+            .filter(houses::name.eq(DEMO_HOUSE_NAME))
+            .count()
+            .get_result::<i64>(&mut conn)
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,862 @@
+// SQLite has no row locks, so writes that read first run in immediate
+// transactions, which take the database write lock up front.
+
+mod model;
+mod schema;
+
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel::{dsl::count_star, prelude::*};
+use diesel_async::{scoped_futures::ScopedFutureExt, RunQueryDsl};
+
+use model::*;
+use super::{missing_house, missing_room, now, record_pool_metrics, Counts, Precondition, Repository, Saved};
+use crate::{
+    database::{unicode_lower, SqliteConnection, SqliteDbConnection, SqlitePool}, error::Error, health_check,
+    migrations, pagination::{self, Cursor}, transfer::{Dump, Transfer, DEMO_HOUSE_NAME}, BoxError
+};
+
+/// Repository over a SQLite connection pool.
+#[derive(Clone)]
+pub struct SqliteRepository {
+    pool: SqlitePool
+}
+
+impl SqliteRepository {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    async fn get_db_connection(&self) -> Result<SqliteDbConnection<'_>, Error> {
+        self.pool.get().await.map_err(Error::from_internal)
+    }
+}
+
+impl Repository for SqliteRepository {
+    async fn list_houses(&self) -> Result<Vec<shared::House>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        houses
+            .select(House::as_select())
+            .order((created_at, id))
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| result.into_iter().map(Into::into).collect())
+    }
+
+    async fn create_house(&self, new_house: shared::NewHouse) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let now = now().naive_utc();
+        let house = House { id: Id(uuid::Uuid::new_v4()), name: new_house.name, created_at: now, updated_at: now };
+
+        diesel::insert_into(houses::table)
+            .values(house)
+            .returning(House::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_house(&self, house_id: uuid::Uuid) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        houses
+            .find(Id(house_id))
+            .select(House::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_house(&self, house_id: uuid::Uuid, patch: shared::HousePatch) -> Result<shared::House, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = houses
+                .find(Id(house_id))
+                .select(House::as_select())
+                .first(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            match patch.name {
+                Some(new_name) if new_name != current.name => rename_house(conn, house_id, new_name).await,
+                _ => Ok(current)
+            }
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn replace_house(
+        &self,
+        house_id: uuid::Uuid,
+        new_house: shared::NewHouse
+    ) -> Result<Saved<shared::House>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = houses
+                .find(Id(house_id))
+                .select(House::as_select())
+                .first(conn)
+                .await
+                .optional()?
+            ;
+
+            match current {
+                Some(current) if current.name == new_house.name => Ok(Saved::Updated(current.into())),
+                Some(_) => rename_house(conn, house_id, new_house.name)
+                    .await
+                    .map(|result| Saved::Updated(result.into())),
+                None => {
+                    let now = now().naive_utc();
+                    let house = House { id: Id(house_id), name: new_house.name, created_at: now, updated_at: now };
+
+                    diesel::insert_into(houses)
+                        .values(house)
+                        .returning(House::as_returning())
+                        .get_result(conn)
+                        .await
+                        .map_err(Error::from_db)
+                        .map(|result| Saved::Created(result.into()))
+                }
+            }
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_house(&self, house_id: uuid::Uuid) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::houses::dsl::*;
+
+        let deleted = diesel::delete(houses.find(Id(house_id)))
+            .execute(&mut conn)
+            .await
+            .map_err(Error::from_db)?;
+
+        if deleted == 0 {
+            return Err(Error::NotFound);
+        }
+
+        Ok(())
+    }
+
+    async fn list_rooms(
+        &self,
+        house_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Room>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_house_exists(&mut conn, house_id).await?;
+
+        use model::rooms::dsl;
+
+        let sort = list_query.sort.unwrap_or_default();
+        let limit = list_query.limit.unwrap_or(shared::DEFAULT_PAGE_LIMIT);
+
+        let mut query = dsl::rooms
+            .filter(dsl::house_id.eq(Id(house_id)))
+            .select(Room::as_select())
+            .limit(i64::from(limit) + 1)
+            .into_boxed();
+
+        if let Some(updated_since) = list_query.updated_since {
+            query = query.filter(dsl::updated_at.ge(updated_since.naive_utc()));
+        }
+
+        if let Some(name) = &list_query.name {
+            query = query.filter(unicode_lower(dsl::name).like(pagination::contains_pattern(&name.to_lowercase())).escape('\\'));
+        }
+
+        let cursor = list_query.cursor.as_deref().map(|cursor| Cursor::decode(cursor, sort)).transpose()?;
+
+        query = match cursor {
+            Some(Cursor::CreatedAt(micros, last_id)) => {
+                let created_at = Cursor::created_at(micros)?;
+                query.filter(
+                    dsl::created_at.gt(created_at).or(dsl::created_at.eq(created_at).and(dsl::id.gt(Id(last_id))))
+                )
+            },
+            Some(Cursor::Name(last_name, last_id)) => {
+                query.filter(dsl::name.gt(last_name.clone()).or(dsl::name.eq(last_name).and(dsl::id.gt(Id(last_id)))))
+            },
+            None => query
+        };
+
+        query = match sort {
+            shared::SortBy::CreatedAt => query.order((dsl::created_at, dsl::id)),
+            shared::SortBy::Name => query.order((dsl::name, dsl::id))
+        };
+
+        query
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| {
+                pagination::into_page(result, limit, |room: &Room| {
+                    Cursor::new(sort, room.created_at, &room.name, room.id.0)
+                })
+            })
+    }
+
+    async fn create_room(&self, house_id: uuid::Uuid, new_room: shared::NewRoom) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_house_exists(&mut conn, house_id).await?;
+
+        let now = now().naive_utc();
+        let room = Room {
+            id: Id(uuid::Uuid::new_v4()),
+            house_id: Id(house_id),
+            name: new_room.name,
+            created_at: now,
+            updated_at: now
+        };
+
+        diesel::insert_into(rooms::table)
+            .values(room)
+            .returning(Room::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_room(&self, room_id: uuid::Uuid) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        rooms
+            .find(Id(room_id))
+            .select(Room::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::RoomPatch
+    ) -> Result<shared::Room, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(Id(room_id))
+                .select(Room::as_select())
+                .first(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.updated_at.and_utc()))?;
+
+            let new_name = match patch.name {
+                Some(new_name) if new_name != current.name => new_name,
+                _ => return Ok(current)
+            };
+
+            diesel::update(rooms.find(Id(room_id)))
+                .set((name.eq(new_name), updated_at.eq(now().naive_utc())))
+                .returning(Room::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn replace_room(
+        &self,
+        room_id: uuid::Uuid,
+        precondition: Precondition,
+        replacement: shared::ReplaceRoom
+    ) -> Result<Saved<shared::Room>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(Id(room_id))
+                .select(Room::as_select())
+                .first(conn)
+                .await
+                .optional()?
+            ;
+
+            match &current {
+                Some(current) => precondition.check(&shared::etag(current.updated_at.and_utc()))?,
+                None if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+                None => {}
+            }
+
+            ensure_house_exists(conn, replacement.house_id)
+                .await
+                .map_err(|err| match err {
+                    Error::NotFound => missing_house(),
+                    err => err
+                })?;
+
+            let now = now().naive_utc();
+
+            if let Some(current) = current {
+                if current.house_id.0 == replacement.house_id && current.name == replacement.name {
+                    return Ok(Saved::Updated(current.into()));
+                }
+
+                return diesel::update(rooms.find(Id(room_id)))
+                    .set((house_id.eq(Id(replacement.house_id)), name.eq(replacement.name), updated_at.eq(now)))
+                    .returning(Room::as_returning())
+                    .get_result(conn)
+                    .await
+                    .map_err(Error::from_db)
+                    .map(|result| Saved::Updated(result.into()));
+            }
+
+            let room = Room {
+                id: Id(room_id),
+                house_id: Id(replacement.house_id),
+                name: replacement.name,
+                created_at: now,
+                updated_at: now
+            };
+
+            diesel::insert_into(rooms)
+                .values(room)
+                .returning(Room::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+                .map(|result| Saved::Created(result.into()))
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_room(&self, room_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::rooms::dsl::*;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = rooms
+                .find(Id(room_id))
+                .select(updated_at)
+                .first::<NaiveDateTime>(conn)
+                .await
+                .optional()?
+                .ok_or(Error::NotFound)?
+            ;
+
+            precondition.check(&shared::etag(current.and_utc()))?;
+
+            diesel::delete(rooms.find(Id(room_id)))
+                .execute(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn list_devices(
+        &self,
+        room_id: uuid::Uuid,
+        list_query: shared::ListQuery
+    ) -> Result<shared::Page<shared::Device>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_room_exists(&mut conn, room_id).await?;
+
+        use model::devices::dsl;
+
+        let sort = list_query.sort.unwrap_or_default();
+        let limit = list_query.limit.unwrap_or(shared::DEFAULT_PAGE_LIMIT);
+
+        let mut query = dsl::devices
+            .filter(dsl::room_id.eq(Id(room_id)))
+            .select(Device::as_select())
+            .limit(i64::from(limit) + 1)
+            .into_boxed();
+
+        if let Some(updated_since) = list_query.updated_since {
+            query = query.filter(dsl::updated_at.ge(updated_since.naive_utc()));
+        }
+
+        if let Some(name) = &list_query.name {
+            query = query.filter(unicode_lower(dsl::name).like(pagination::contains_pattern(&name.to_lowercase())).escape('\\'));
+        }
+
+        let cursor = list_query.cursor.as_deref().map(|cursor| Cursor::decode(cursor, sort)).transpose()?;
+
+        query = match cursor {
+            Some(Cursor::CreatedAt(micros, last_id)) => {
+                let created_at = Cursor::created_at(micros)?;
+                query.filter(
+                    dsl::created_at.gt(created_at).or(dsl::created_at.eq(created_at).and(dsl::id.gt(Id(last_id))))
+                )
+            },
+            Some(Cursor::Name(last_name, last_id)) => {
+                query.filter(dsl::name.gt(last_name.clone()).or(dsl::name.eq(last_name).and(dsl::id.gt(Id(last_id)))))
+            },
+            None => query
+        };
+
+        query = match sort {
+            shared::SortBy::CreatedAt => query.order((dsl::created_at, dsl::id)),
+            shared::SortBy::Name => query.order((dsl::name, dsl::id))
+        };
+
+        query
+            .load(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(|result| {
+                pagination::into_page(result, limit, |device: &Device| {
+                    Cursor::new(sort, device.created_at, &device.name, device.id.0)
+                })
+            })
+    }
+
+    async fn create_device(
+        &self,
+        room_id: uuid::Uuid,
+        new_device: shared::NewDevice
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        ensure_room_exists(&mut conn, room_id).await?;
+
+        diesel::insert_into(devices::table)
+            .values(new_device_row(uuid::Uuid::new_v4(), room_id, new_device))
+            .returning(Device::as_returning())
+            .get_result(&mut conn)
+            .await
+            .map_err(Error::from_db)
+            .map(Into::into)
+    }
+
+    async fn get_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        use model::devices::dsl;
+
+        dsl::devices
+            .filter(dsl::room_id.eq(Id(room_id)))
+            .filter(dsl::id.eq(Id(device_id)))
+            .select(Device::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)
+            .map(Into::into)
+    }
+
+    async fn patch_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        patch: shared::DevicePatch
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = find_device(conn, room_id, device_id).await?.ok_or(Error::NotFound)?;
+
+            precondition.check(&shared::etag(current.updated_at.and_utc()))?;
+
+            let kind = patch.apply_to_kind(&current.kind).map_err(Error::Validation)?;
+            let name = patch.name.unwrap_or_else(|| current.name.clone());
+
+            update_device(conn, current, name, kind).await
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn replace_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        precondition: Precondition,
+        new_device: shared::NewDevice
+    ) -> Result<Saved<shared::Device>, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = find_device(conn, room_id, device_id).await?;
+
+            match &current {
+                Some(current) => precondition.check(&shared::etag(current.updated_at.and_utc()))?,
+                None if precondition.is_conditional() => return Err(Error::PreconditionFailed),
+                None => {}
+            }
+
+            if let Some(current) = current {
+                return update_device(conn, current, new_device.name, new_device.kind)
+                    .await
+                    .map(|result| Saved::Updated(result.into()));
+            }
+
+            ensure_room_exists(conn, room_id).await?;
+
+            diesel::insert_into(devices::table)
+                .values(new_device_row(device_id, room_id, new_device))
+                .returning(Device::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+                .map(|result| Saved::Created(result.into()))
+        }.scope_boxed())
+            .await
+    }
+
+    async fn delete_device(&self, room_id: uuid::Uuid, device_id: uuid::Uuid, precondition: Precondition) -> Result<(), Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = find_device(conn, room_id, device_id).await?.ok_or(Error::NotFound)?;
+
+            precondition.check(&shared::etag(current.updated_at.and_utc()))?;
+
+            diesel::delete(devices::table.find(current.id))
+                .execute(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await?;
+
+        Ok(())
+    }
+
+    async fn move_device(
+        &self,
+        room_id: uuid::Uuid,
+        device_id: uuid::Uuid,
+        target_room_id: uuid::Uuid
+    ) -> Result<shared::Device, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        conn.immediate_transaction::<_, Error, _>(|conn| async move {
+            let current = find_device(conn, room_id, device_id).await?.ok_or(Error::NotFound)?;
+
+            if target_room_id == room_id {
+                return Ok(current);
+            }
+
+            ensure_room_exists(conn, target_room_id)
+                .await
+                .map_err(|err| match err {
+                    Error::NotFound => missing_room(),
+                    err => err
+                })?;
+
+            diesel::update(devices::table.find(current.id))
+                .set((devices::room_id.eq(Id(target_room_id)), devices::updated_at.eq(now().naive_utc())))
+                .returning(Device::as_returning())
+                .get_result(conn)
+                .await
+                .map_err(Error::from_db)
+        }.scope_boxed())
+            .await
+            .map(Into::into)
+    }
+
+    async fn report(&self, house_id: uuid::Uuid) -> Result<shared::Report, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let house = houses::table
+            .find(Id(house_id))
+            .select(House::as_select())
+            .first(&mut conn)
+            .await
+            .optional()
+            .map_err(Error::from_db)?
+            .ok_or(Error::NotFound)?
+        ;
+
+        let rows = rooms::table
+            .filter(rooms::house_id.eq(Id(house_id)))
+            .left_join(devices::table)
+            .select((Room::as_select(), Option::<Device>::as_select()))
+            .order((rooms::name, rooms::id, devices::name))
+            .load::<(Room, Option<Device>)>(&mut conn)
+            .await
+            .map_err(Error::from_db)?;
+
+        let mut report_rooms: Vec<shared::RoomReport> = Vec::new();
+
+        for (room, device) in rows {
+            if report_rooms.last().is_none_or(|last| last.id != room.id.0) {
+                report_rooms.push(shared::RoomReport { id: room.id.0, name: room.name, devices: Vec::new() });
+            }
+
+            if let (Some(device), Some(last)) = (device, report_rooms.last_mut()) {
+                last.devices.push(device.into());
+            }
+        }
+
+        Ok(shared::Report {
+            house_id: house.id.0,
+            house: house.name,
+            total_rooms: report_rooms.len(),
+            total_devices: report_rooms.iter().map(|room| room.devices.len()).sum(),
+            rooms: report_rooms,
+        })
+    }
+
+    async fn counts(&self) -> Result<Counts, Error> {
+        let mut conn = self.get_db_connection().await?;
+
+        let total_houses = houses::table.count().get_result::<i64>(&mut conn).await?;
+        let total_rooms = rooms::table.count().get_result::<i64>(&mut conn).await?;
+        let devices_by_kind: HashMap<String, u64> = devices::table
+            .group_by(devices::kind)
+            .select((devices::kind, count_star()))
+            .load::<(String, i64)>(&mut conn)
+            .await?
+            .into_iter()
+            .map(|(kind, count)| (kind, count as u64))
+            .collect();
+
+        Ok(Counts { houses: total_houses as u64, rooms: total_rooms as u64, devices: devices_by_kind })
+    }
+
+    /// Ready when a pooled connection answers queries and the schema is current.
+    async fn readiness(&self) -> Vec<shared::HealthCheck> {
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(err) => return vec![health_check("database", Err::<Option<String>, _>(err))]
+        };
+
+        let database = diesel::sql_query("SELECT 1").execute(&mut conn).await.map(|_| None);
+        let migrations = migrations::pending_on_sqlite(&mut conn).await.map(|pending| {
+            (!pending.is_empty()).then(|| format!("{} pending migration(s)", pending.len()))
+        });
+
+        vec![health_check("database", database), health_check("migrations", migrations)]
+    }
+
+    fn record_metrics(&self) {
+        record_pool_metrics(&self.pool);
+    }
+}
+
+async fn ensure_house_exists(conn: &mut SqliteConnection, house_id: uuid::Uuid) -> Result<(), Error> {
+    use model::houses::dsl;
+
+    dsl::houses
+        .find(Id(house_id))
+        .select(dsl::id)
+        .first::<Id>(conn)
+        .await
+        .optional()
+        .map_err(Error::from_db)?
+        .ok_or(Error::NotFound)
+        .map(|_| ())
+}
+
+async fn ensure_room_exists(conn: &mut SqliteConnection, room_id: uuid::Uuid) -> Result<(), Error> {
+    use model::rooms::dsl;
+
+    dsl::rooms
+        .find(Id(room_id))
+        .select(dsl::id)
+        .first::<Id>(conn)
+        .await
+        .optional()
+        .map_err(Error::from_db)?
+        .ok_or(Error::NotFound)
+        .map(|_| ())
+}
+
+async fn rename_house(conn: &mut SqliteConnection, house_id: uuid::Uuid, new_name: String) -> Result<House, Error> {
+    use model::houses::dsl::*;
+
+    diesel::update(houses.find(Id(house_id)))
+        .set((name.eq(new_name), updated_at.eq(now().naive_utc())))
+        .returning(House::as_returning())
+        .get_result(conn)
+        .await
+        .map_err(Error::from_db)
+}
+
+async fn find_device(
+    conn: &mut SqliteConnection,
+    room_id: uuid::Uuid,
+    device_id: uuid::Uuid
+) -> Result<Option<Device>, Error> {
+    use model::devices::dsl;
+
+    dsl::devices
+        .filter(dsl::room_id.eq(Id(room_id)))
+        .filter(dsl::id.eq(Id(device_id)))
+        .select(Device::as_select())
+        .first(conn)
+        .await
+        .optional()
+        .map_err(Error::from_db)
+}
+
+/// Saves the new name and kind, `updated_at` only moves when one of them changed.
+async fn update_device(
+    conn: &mut SqliteConnection,
+    current: Device,
+    name: String,
+    kind: shared::DeviceKind
+) -> Result<Device, Error> {
+    if current.name == name && current.kind == kind {
+        return Ok(current);
+    }
+
+    diesel::update(devices::table.find(current.id))
+        .set((devices::name.eq(name), DeviceKindColumns::from(kind), devices::updated_at.eq(now().naive_utc())))
+        .returning(Device::as_returning())
+        .get_result(conn)
+        .await
+        .map_err(Error::from_db)
+}
+
+fn new_device_row(device_id: uuid::Uuid, room_id: uuid::Uuid, new_device: shared::NewDevice) -> NewDevice {
+    let now = now().naive_utc();
+
+    NewDevice {
+        id: Id(device_id),
+        room_id: Id(room_id),
+        name: new_device.name,
+        kind: new_device.kind.into(),
+        created_at: now,
+        updated_at: now
+    }
+}
+
+impl Transfer for SqliteRepository {
+    async fn export(&self, house_ids: &[uuid::Uuid]) -> Result<Dump, BoxError> {
+        let mut conn = self.pool.get().await?;
+
+        let mut query = houses::table
+            .select(House::as_select())
+            .order((houses::created_at, houses::id))
+            .into_boxed();
+
+        if !house_ids.is_empty() {
+            query = query.filter(houses::id.eq_any(house_ids.iter().copied().map(Id)));
+        }
+
+        let house_rows = query.load(&mut conn).await?;
+
+        let room_rows = rooms::table
+            .filter(rooms::house_id.eq_any(house_rows.iter().map(|house| house.id)))
+            .select(Room::as_select())
+            .order((rooms::created_at, rooms::id))
+            .load(&mut conn)
+            .await?;
+
+        let device_rows = devices::table
+            .filter(devices::room_id.eq_any(room_rows.iter().map(|room| room.id)))
+            .select(Device::as_select())
+            .order((devices::created_at, devices::id))
+            .load(&mut conn)
+            .await?;
+
+        Dump::from_rows(
+            house_ids,
+            house_rows.into_iter().map(Into::into).collect(),
+            room_rows.into_iter().map(Into::into).collect(),
+            device_rows.into_iter().map(Into::into).collect()
+        )
+    }
+
+    async fn import(&self, dump: Dump) -> Result<(usize, usize, usize), BoxError> {
+        dump.validate()?;
+
+        let mut conn = self.pool.get().await?;
+
+        let counts = conn.immediate_transaction::<_, diesel::result::Error, _>(|conn| async move {
+            let (mut total_rooms, mut total_devices) = (0, 0);
+            let now = now().naive_utc();
+
+            for house in &dump.houses {
+                let house_id = Id(house.id.unwrap_or_else(uuid::Uuid::new_v4));
+
+                diesel::insert_into(houses::table)
+                    .values(House { id: house_id, name: house.name.clone(), created_at: now, updated_at: now })
+                    .execute(conn)
+                    .await?;
+
+                for room in &house.rooms {
+                    let room_id = Id(room.id.unwrap_or_else(uuid::Uuid::new_v4));
+
+                    diesel::insert_into(rooms::table)
+                        .values(Room { id: room_id, house_id, name: room.name.clone(), created_at: now, updated_at: now })
+                        .execute(conn)
+                        .await?;
+
+                    for device in &room.devices {
+                        let new_device = shared::NewDevice { name: device.name.clone(), kind: device.kind.clone() };
+                        let device_id = device.id.unwrap_or_else(uuid::Uuid::new_v4);
+
+                        diesel::insert_into(devices::table)
+                            .values(new_device_row(device_id, room_id.0, new_device))
+                            .execute(conn)
+                            .await?;
+                    }
+
+                    total_devices += room.devices.len();
+                }
+
+                total_rooms += house.rooms.len();
+            }
+
+            Ok((dump.houses.len(), total_rooms, total_devices))
+        }.scope_boxed())
+            .await?;
+
+        Ok(counts)
+    }
+
+    async fn demo_exists(&self) -> Result<bool, BoxError> {
+        let mut conn = self.pool.get().await?;
+
+        let count = houses::table
+            .filter(houses::name.eq(DEMO_HOUSE_NAME))
+            .count()
+            .get_result::<i64>(&mut conn)
+            .await?;
+
+        Ok(count > 0)
+    }
+}
@@ -0,0 +1,150 @@
+// Timestamps are set by the application in UTC, there are no triggers or defaults.
+
+use chrono::NaiveDateTime;
+use diesel::{
+    backend::Backend,
+    deserialize::{self, FromSql, FromSqlRow},
+    expression::AsExpression,
+    prelude::*,
+    serialize::{self, IsNull, Output, ToSql},
+    sql_types::Text,
+    sqlite::Sqlite
+};
+
+pub use super::schema::*;
+use crate::model;
+
+/// UUID stored as its hyphenated text form, which sorts like the bytes do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, AsExpression, FromSqlRow)]
+#[diesel(sql_type = Text)]
+pub struct Id(pub uuid::Uuid);
+
+impl ToSql<Text, Sqlite> for Id {
+    fn to_sql<'b>(&'b self, out: &mut Output<'b, '_, Sqlite>) -> serialize::Result {
+        out.set_value(self.0.to_string());
+        Ok(IsNull::No)
+    }
+}
+
+impl FromSql<Text, Sqlite> for Id {
+    fn from_sql(bytes: <Sqlite as Backend>::RawValue<'_>) -> deserialize::Result<Self> {
+        let text = <String as FromSql<Text, Sqlite>>::from_sql(bytes)?;
+        Ok(Self(text.parse()?))
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = houses)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct House {
+    pub id: Id,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<House> for shared::House {
+    fn from(value: House) -> Self {
+        Self {
+            id: value.id.0,
+            name: value.name,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
+    }
+}
+
+#[derive(Queryable, Selectable, Insertable)]
+#[diesel(table_name = rooms)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Room {
+    pub id: Id,
+    pub house_id: Id,
+    pub name: String,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<Room> for shared::Room {
+    fn from(value: Room) -> Self {
+        Self {
+            id: value.id.0,
+            house_id: value.house_id.0,
+            name: value.name,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
+    }
+}
+
+#[derive(Queryable, Selectable)]
+#[diesel(table_name = devices)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct Device {
+    pub id: Id,
+    pub room_id: Id,
+    pub name: String,
+    #[diesel(
+        select_expression = (devices::kind, devices::is_on, devices::power, devices::temperature),
+        deserialize_as = DeviceKindColumns
+    )]
+    pub kind: shared::DeviceKind,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl From<Device> for shared::Device {
+    fn from(value: Device) -> Self {
+        Self {
+            id: value.id.0,
+            room_id: value.room_id.0,
+            name: value.name,
+            kind: value.kind,
+            created_at: value.created_at.and_utc(),
+            updated_at: value.updated_at.and_utc()
+        }
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = devices)]
+#[diesel(check_for_backend(Sqlite))]
+pub struct NewDevice {
+    pub id: Id,
+    pub room_id: Id,
+    pub name: String,
+    #[diesel(embed)]
+    pub kind: DeviceKindColumns,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+/// SQLite twin of [`model::DeviceKindColumns`], conversions go through it.
+#[derive(Queryable, Insertable, AsChangeset)]
+#[diesel(table_name = devices)]
+#[diesel(check_for_backend(Sqlite))]
+#[diesel(treat_none_as_null = true)]
+pub struct DeviceKindColumns {
+    pub kind: String,
+    pub is_on: Option<bool>,
+    pub power: Option<f64>,
+    pub temperature: Option<f64>,
+}
+
+impl From<shared::DeviceKind> for DeviceKindColumns {
+    fn from(value: shared::DeviceKind) -> Self {
+        let model::DeviceKindColumns { kind, is_on, power, temperature } = value.into();
+
+        Self { kind, is_on, power, temperature }
+    }
+}
+
+impl TryFrom<DeviceKindColumns> for shared::DeviceKind {
+    type Error = String;
+
+    fn try_from(value: DeviceKindColumns) -> Result<Self, Self::Error> {
+        let DeviceKindColumns { kind, is_on, power, temperature } = value;
+
+        model::DeviceKindColumns { kind, is_on, power, temperature }.try_into()
+    }
+}
@@ -0,0 +1,43 @@
+// Tables of `migrations_sqlite`, ids are UUID strings.
+
+diesel::table! {
+    devices (id) {
+        id -> Text,
+        room_id -> Text,
+        name -> Text,
+        kind -> Text,
+        is_on -> Nullable<Bool>,
+        power -> Nullable<Double>,
+        temperature -> Nullable<Double>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    houses (id) {
+        id -> Text,
+        name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    rooms (id) {
+        id -> Text,
+        house_id -> Text,
+        name -> Text,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::joinable!(devices -> rooms (room_id));
+diesel::joinable!(rooms -> houses (house_id));
+
+diesel::allow_tables_to_appear_in_same_query!(
+    devices,
+    houses,
+    rooms,
+);
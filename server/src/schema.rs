@@ -7,6 +7,10 @@ diesel::table! {
         name -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        kind -> Varchar,
+        is_on -> Nullable<Bool>,
+        power -> Nullable<Float8>,
+        temperature -> Nullable<Float8>,
     }
 }
 
@@ -25,10 +29,12 @@ diesel::table! {
         name -> Varchar,
         created_at -> Timestamp,
         updated_at -> Timestamp,
+        house_id -> Uuid,
     }
 }
 
 diesel::joinable!(devices -> rooms (room_id));
+diesel::joinable!(rooms -> houses (house_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
     devices,
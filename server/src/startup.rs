@@ -0,0 +1,85 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use crate::{database::{Database, UnsupportedSqlite}, BoxError};
+
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(thiserror::Error, Debug)]
+pub enum StartupError {
+    #[error("Database url is not set, use --database-url or DATABASE_URL")]
+    MissingDatabaseUrl,
+    #[error(transparent)]
+    UnsupportedDatabase(#[from] UnsupportedSqlite),
+    #[error("Can't connect to the database after {attempts} attempt(s): {source}")]
+    Database { attempts: u32, source: diesel::ConnectionError },
+    #[error("Can't create the database pool: {0}")]
+    Pool(BoxError),
+    #[error("Can't install the metrics recorder: {0}")]
+    Metrics(BoxError),
+    #[error("Can't apply migrations: {0}")]
+    Migrations(BoxError),
+    #[error(
+        "Database schema is behind, {} pending migration(s): {}. \
+        Run `server migrate run` or start with --auto-migrate",
+        .0.len(),
+        .0.join(", ")
+    )]
+    SchemaBehind(Vec<String>),
+    #[error("Can't listen on {addr}: {source}")]
+    Bind { addr: SocketAddr, source: io::Error },
+    #[error("Server failed: {0}")]
+    Serve(io::Error),
+}
+
+/// Waits for the database to accept connections, so the server can start
+/// alongside it. Delays double between attempts up to `MAX_RETRY_DELAY`.
+pub async fn wait_for_database(database: &Database, retries: u32) -> Result<(), StartupError> {
+    let mut delay = INITIAL_RETRY_DELAY;
+    let mut attempts = 0;
+
+    loop {
+        attempts += 1;
+
+        match database.check_connection().await {
+            Ok(_) => return Ok(()),
+            Err(source) if attempts > retries => return Err(StartupError::Database { attempts, source }),
+            Err(err) => {
+                tracing::warn!("Database is not available ({err}), retrying in {delay:?}");
+                tokio::time::sleep(delay).await;
+                delay = (delay * 2).min(MAX_RETRY_DELAY);
+            }
+        }
+    }
+}
+
+/// Resolves on Ctrl+C or, on unix, SIGTERM.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Can't listen for Ctrl+C: {err}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            },
+            Err(err) => {
+                tracing::error!("Can't listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
@@ -0,0 +1,161 @@
+use std::{io::{self, IsTerminal}, sync::Arc, time::{Duration, Instant}};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response}
+};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tracing::{field, Span};
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    config::{LogConfig, LogFormat}, repository::{Counts, Repository}, AppState, BoxError
+};
+
+const REQUEST_DURATION: &str = "http_request_duration_seconds";
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const UPKEEP_INTERVAL: Duration = Duration::from_secs(5);
+/// Scrapes still get HTTP and pool metrics while the database hangs.
+const COUNTS_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Installs the global tracing subscriber, records of `log` based
+/// dependencies are forwarded to it. Logs go to stderr, stdout is left
+/// for command output such as exports.
+pub fn init_tracing(config: &LogConfig) {
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&config.level))
+        .with_writer(io::stderr)
+        .with_ansi(io::stderr().is_terminal());
+
+    match config.format {
+        LogFormat::Text => builder.init(),
+        LogFormat::Json => builder.json().init(),
+    }
+}
+
+/// Span wrapping a request, events logged while handling it carry its fields.
+pub fn make_request_span(req: &Request) -> Span {
+    let request_id = req
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let route = req.extensions().get::<MatchedPath>().map(MatchedPath::as_str);
+
+    let span = tracing::info_span!(
+        "request",
+        method = %req.method(),
+        route = route.unwrap_or("unmatched"),
+        request_id,
+        house_id = field::Empty,
+        room_id = field::Empty,
+        device_id = field::Empty,
+    );
+
+    if let Some(route) = route {
+        record_path_ids(&span, route, req.uri().path());
+    }
+
+    span
+}
+
+/// Fills the `*_id` span fields from path parameters, each named after the
+/// collection segment in front of it, e.g. `/rooms/{id}` gives `room_id`.
+fn record_path_ids(span: &Span, route: &str, path: &str) {
+    let mut collection = "";
+
+    for (template, value) in route.split('/').zip(path.split('/')) {
+        if !template.starts_with('{') {
+            collection = template;
+            continue;
+        }
+
+        let field = match collection {
+            "houses" => "house_id",
+            "rooms" => "room_id",
+            "devices" => "device_id",
+            _ => continue
+        };
+
+        span.record(field, value);
+    }
+}
+
+/// Installs the global Prometheus recorder.
+pub fn install_recorder() -> Result<PrometheusHandle, BoxError> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full(REQUEST_DURATION.to_string()), LATENCY_BUCKETS)?
+        .install_recorder()?;
+
+    describe_metrics();
+
+    // Without the exporter's own listener nothing drains histograms, so it's done here.
+    let upkeep = handle.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UPKEEP_INTERVAL);
+        loop {
+            interval.tick().await;
+            upkeep.run_upkeep();
+        }
+    });
+
+    Ok(handle)
+}
+
+fn describe_metrics() {
+    metrics::describe_counter!("http_requests_total", "HTTP requests by method, route and status");
+    metrics::describe_histogram!(REQUEST_DURATION, metrics::Unit::Seconds, "HTTP request latency by method, route and status");
+    metrics::describe_gauge!("db_pool_connections", "Connections managed by the database pool");
+    metrics::describe_gauge!("db_pool_idle_connections", "Idle connections in the database pool");
+    metrics::describe_counter!("db_pool_gets_total", "Connection checkouts by whether they had to wait");
+    metrics::describe_gauge!("db_pool_wait_seconds", "Total time spent waiting for a pooled connection");
+    metrics::describe_gauge!("smart_house_houses", "Number of houses");
+    metrics::describe_gauge!("smart_house_rooms", "Number of rooms");
+    metrics::describe_gauge!("smart_house_devices", "Number of devices by kind");
+}
+
+/// Counts requests and their latency by route template rather than the
+/// concrete path, so ids don't blow up the label cardinality.
+pub async fn track_requests(req: Request, next: Next) -> Response {
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or_else(|| "unmatched".to_string(), |path| path.as_str().to_string());
+    let method = req.method().to_string();
+    let start = Instant::now();
+
+    let response = next.run(req).await;
+
+    let labels = [("method", method), ("route", route), ("status", response.status().as_u16().to_string())];
+    metrics::counter!("http_requests_total", &labels).increment(1);
+    metrics::histogram!(REQUEST_DURATION, &labels).record(start.elapsed().as_secs_f64());
+
+    response
+}
+
+pub async fn render<R: Repository>(State(state): State<Arc<AppState<R>>>) -> Response {
+    state.repository.record_metrics();
+
+    match tokio::time::timeout(COUNTS_TIMEOUT, state.repository.counts()).await {
+        Ok(Ok(counts)) => record_entity_counts(&counts),
+        Ok(Err(err)) => tracing::warn!("Can't count entities for metrics: {err}"),
+        Err(_) => tracing::warn!("Counting entities for metrics timed out")
+    }
+
+    // Only routed when the state has a handle
+    let body = state.metrics.as_ref().map(PrometheusHandle::render).unwrap_or_default();
+
+    ([(header::CONTENT_TYPE, "text/plain; version=0.0.4")], body).into_response()
+}
+
+fn record_entity_counts(counts: &Counts) {
+    metrics::gauge!("smart_house_houses").set(counts.houses as f64);
+    metrics::gauge!("smart_house_rooms").set(counts.rooms as f64);
+
+    for kind in shared::DeviceKind::NAMES {
+        let count = counts.devices.get(kind).copied().unwrap_or_default();
+        metrics::gauge!("smart_house_devices", "kind" => kind).set(count as f64);
+    }
+}
@@ -0,0 +1,152 @@
+// Export and import of whole houses, used by the `export`, `import` and `seed` commands.
+
+use serde::{Deserialize, Serialize};
+use shared::Validate;
+
+use crate::BoxError;
+
+pub const DEMO_HOUSE_NAME: &str = "Demo house";
+
+#[derive(Serialize, Deserialize)]
+pub struct Dump {
+    pub houses: Vec<HouseDump>,
+}
+
+/// Ids are optional on import, missing ones are generated.
+#[derive(Serialize, Deserialize)]
+pub struct HouseDump {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<uuid::Uuid>,
+    pub name: String,
+    #[serde(default)]
+    pub rooms: Vec<RoomDump>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RoomDump {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<uuid::Uuid>,
+    pub name: String,
+    #[serde(default)]
+    pub devices: Vec<DeviceDump>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct DeviceDump {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<uuid::Uuid>,
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: shared::DeviceKind,
+}
+
+impl Dump {
+    pub fn demo() -> Self {
+        let device = |name: &str, kind| DeviceDump { id: None, name: name.to_string(), kind };
+        let room = |name: &str, devices| RoomDump { id: None, name: name.to_string(), devices };
+
+        Self {
+            houses: vec![HouseDump {
+                id: None,
+                name: DEMO_HOUSE_NAME.to_string(),
+                rooms: vec![
+                    room("Living room", vec![
+                        device("TV socket", shared::DeviceKind::Socket { is_on: true, power: 120.0 }),
+                        device("Thermometer", shared::DeviceKind::Thermometer { temperature: 22.5 }),
+                    ]),
+                    room("Kitchen", vec![
+                        device("Kettle socket", shared::DeviceKind::Socket { is_on: false, power: 0.0 }),
+                    ]),
+                    room("Bedroom", vec![
+                        device("Thermometer", shared::DeviceKind::Thermometer { temperature: 20.0 }),
+                    ]),
+                ],
+            }],
+        }
+    }
+
+    /// Runs the API validation rules over every entity, so an import can't
+    /// store anything the API would reject.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut errors = Vec::new();
+        let mut collect = |path: String, result: Result<(), Vec<shared::FieldError>>| {
+            if let Err(field_errors) = result {
+                errors.extend(
+                    field_errors
+                        .into_iter()
+                        .map(|error| format!("{path}.{}: {}", error.field, error.message))
+                );
+            }
+        };
+
+        for (i, house) in self.houses.iter().enumerate() {
+            let path = format!("houses[{i}]");
+            collect(path.clone(), shared::NewHouse { name: house.name.clone() }.validate());
+
+            for (j, room) in house.rooms.iter().enumerate() {
+                let path = format!("{path}.rooms[{j}]");
+                collect(path.clone(), shared::NewRoom { name: room.name.clone() }.validate());
+
+                for (k, device) in room.devices.iter().enumerate() {
+                    let new_device = shared::NewDevice { name: device.name.clone(), kind: device.kind.clone() };
+                    collect(format!("{path}.devices[{k}]"), new_device.validate());
+                }
+            }
+        }
+
+        if errors.is_empty() { Ok(()) } else { Err(errors.join("\n")) }
+    }
+
+    /// Nests loaded rows, each list ordered by creation. Fails when one of
+    /// the requested houses wasn't found.
+    pub fn from_rows(
+        house_ids: &[uuid::Uuid],
+        houses: Vec<shared::House>,
+        rooms: Vec<shared::Room>,
+        devices: Vec<shared::Device>
+    ) -> Result<Self, BoxError> {
+        if let Some(missing) = house_ids.iter().find(|&&house_id| houses.iter().all(|house| house.id != house_id)) {
+            return Err(format!("House {missing} not found").into());
+        }
+
+        let houses = houses
+            .into_iter()
+            .map(|house| HouseDump {
+                id: Some(house.id),
+                name: house.name,
+                rooms: rooms
+                    .iter()
+                    .filter(|room| room.house_id == house.id)
+                    .map(|room| RoomDump {
+                        id: Some(room.id),
+                        name: room.name.clone(),
+                        devices: devices
+                            .iter()
+                            .filter(|device| device.room_id == room.id)
+                            .map(|device| DeviceDump {
+                                id: Some(device.id),
+                                name: device.name.clone(),
+                                kind: device.kind.clone(),
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self { houses })
+    }
+}
+
+/// Bulk access to a database for the `export`, `import` and `seed` commands.
+pub trait Transfer {
+    /// Loads the given houses, or all of them when `house_ids` is empty.
+    fn export(&self, house_ids: &[uuid::Uuid]) -> impl Future<Output = Result<Dump, BoxError>> + Send;
+
+    /// Inserts everything in one transaction, so a failed import leaves no partial houses.
+    /// Returns the number of created houses, rooms and devices.
+    fn import(&self, dump: Dump) -> impl Future<Output = Result<(usize, usize, usize), BoxError>> + Send;
+
+    /// Whether the demo house is already there, so seeding twice is a no-op.
+    fn demo_exists(&self) -> impl Future<Output = Result<bool, BoxError>> + Send;
+}
@@ -0,0 +1,159 @@
+// Test fixtures: every test gets its own server behind an in-process client.
+//
+// The server runs on the in-memory store, so the suite needs no database or
+// network. With TEST_DATABASE_URL set to a Postgres url, each test instead
+// creates a scratch database next to the one in the url and drops it afterwards.
+// With TEST_DATABASE_URL=sqlite:// each test gets a SQLite file in the temp
+// directory, removed afterwards.
+
+#![allow(dead_code)]
+
+use axum::Router;
+use client::{Client, DeviceKind, Device, House, NewDevice, NewHouse, NewRoom, Room, ServiceTransport};
+use std::path::PathBuf;
+
+use diesel_async::{AsyncConnection, AsyncPgConnection, RunQueryDsl};
+use server::{
+    config::DatabaseConfig,
+    database, migrations,
+    repository::{MemoryRepository, PgRepository, SqliteRepository},
+    AppState
+};
+
+/// Host of the client urls, requests never leave the process.
+const API_URL: &str = "http://localhost";
+
+pub type TestClient = Client<ServiceTransport<Router>>;
+
+pub struct TestApp {
+    pub client: TestClient,
+    _database: Option<TestDatabase>,
+}
+
+pub async fn spawn_app() -> TestApp {
+    let (router, database) = match std::env::var("TEST_DATABASE_URL") {
+        Ok(url) if url.starts_with("sqlite:") => {
+            let (database, path) = TestDatabase::create_sqlite().await;
+            let pool = database::sqlite_pool(&path, &DatabaseConfig::default()).await.unwrap();
+
+            (server::router(AppState::new(SqliteRepository::new(pool))), Some(database))
+        },
+        Ok(url) => {
+            let (database, url) = TestDatabase::create_postgres(&url).await;
+            let pool = database::pg_pool(&url, &DatabaseConfig::default()).await.unwrap();
+
+            (server::router(AppState::new(PgRepository::new(pool))), Some(database))
+        },
+        Err(_) => (server::router(AppState::new(MemoryRepository::new())), None)
+    };
+
+    let client = Client::with_transport(API_URL.to_string(), ServiceTransport::new(router)).unwrap();
+
+    TestApp { client, _database: database }
+}
+
+/// Database created with the schema applied, dropped with the value.
+enum TestDatabase {
+    Postgres { admin_url: String, name: String },
+    Sqlite { path: PathBuf },
+}
+
+impl TestDatabase {
+    /// Returns the url of the new database.
+    async fn create_postgres(admin_url: &str) -> (Self, String) {
+        let name = format!("test_{}", uuid::Uuid::new_v4().simple());
+        let (server_url, _) = admin_url.rsplit_once('/').expect("TEST_DATABASE_URL must be a Postgres url");
+        let url = format!("{server_url}/{name}");
+
+        let mut conn = AsyncPgConnection::establish(admin_url).await.unwrap();
+        diesel::sql_query(format!(
+            "CREATE DATABASE {name} ENCODING 'UTF8' LC_COLLATE 'C' LC_CTYPE 'C.UTF-8' TEMPLATE template0"
+        )).execute(&mut conn).await.unwrap();
+
+        migrations::run(&url).await.unwrap();
+
+        (Self::Postgres { admin_url: admin_url.to_string(), name }, url)
+    }
+
+    /// Returns the path of the new database file.
+    async fn create_sqlite() -> (Self, String) {
+        let path = std::env::temp_dir().join(format!("test_{}.db", uuid::Uuid::new_v4().simple()));
+        let path_str = path.to_str().expect("temp directory must be valid UTF-8").to_string();
+
+        migrations::run(&format!("sqlite://{path_str}")).await.unwrap();
+
+        (Self::Sqlite { path }, path_str)
+    }
+}
+
+impl Drop for TestDatabase {
+    fn drop(&mut self) {
+        match self {
+            Self::Postgres { admin_url, name } => drop_postgres(admin_url, name),
+            Self::Sqlite { path } => {
+                // WAL mode leaves the log and shared memory files next to the database
+                for suffix in ["", "-wal", "-shm"] {
+                    let mut file = path.clone().into_os_string();
+                    file.push(suffix);
+                    let _ = std::fs::remove_file(file);
+                }
+            }
+        }
+    }
+}
+
+/// Runs on a thread of its own, the test runtime can't be blocked on from here.
+fn drop_postgres(admin_url: &str, name: &str) {
+    let admin_url = admin_url.to_string();
+    let query = format!("DROP DATABASE IF EXISTS {name} WITH (FORCE)");
+
+    let result = std::thread::spawn(move || {
+        tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on(async {
+            let mut conn = AsyncPgConnection::establish(&admin_url).await?;
+            diesel::sql_query(query).execute(&mut conn).await?;
+            Ok::<_, server::BoxError>(())
+        })
+    })
+        .join();
+
+    if let Ok(Err(err)) = result {
+        eprintln!("Can't drop test database {name}: {err}");
+    }
+}
+
+pub async fn add_house(client: &TestClient, name: &str) -> House {
+    client.add_house(&NewHouse { name: name.to_string() }).await.unwrap()
+}
+
+pub async fn add_room(client: &TestClient, house_id: uuid::Uuid, name: &str) -> Room {
+    client.add_room(house_id, &NewRoom { name: name.to_string() }).await.unwrap()
+}
+
+pub async fn add_device(client: &TestClient, room_id: uuid::Uuid, device: NewDevice) -> Device {
+    client.add_device(room_id, &device).await.unwrap()
+}
+
+pub fn socket(name: &str, is_on: bool, power: f64) -> NewDevice {
+    NewDevice { name: name.to_string(), kind: DeviceKind::Socket { is_on, power } }
+}
+
+pub fn thermometer(name: &str, temperature: f64) -> NewDevice {
+    NewDevice { name: name.to_string(), kind: DeviceKind::Thermometer { temperature } }
+}
+
+/// A house with a kitchen holding a socket and a thermometer.
+pub struct Kitchen {
+    pub house: House,
+    pub room: Room,
+    pub socket: Device,
+    pub thermometer: Device,
+}
+
+pub async fn kitchen(client: &TestClient) -> Kitchen {
+    let house = add_house(client, "Home").await;
+    let room = add_room(client, house.id, "Kitchen").await;
+    let socket = add_device(client, room.id, socket("Kettle", false, 0.0)).await;
+    let thermometer = add_device(client, room.id, thermometer("Thermometer", 21.5)).await;
+
+    Kitchen { house, room, socket, thermometer }
+}
@@ -0,0 +1,188 @@
+mod common;
+
+use client::{DeviceKind, DevicePatch, Error, ListQuery, SortBy};
+use common::{add_device, add_house, add_room, kitchen, socket, spawn_app, thermometer};
+use futures::TryStreamExt;
+
+#[tokio::test]
+async fn creates_and_lists_devices() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    assert_eq!(kitchen.socket.room_id, kitchen.room.id);
+    assert_eq!(kitchen.socket.kind, DeviceKind::Socket { is_on: false, power: 0.0 });
+
+    let fetched = app.client.get_device(kitchen.room.id, kitchen.thermometer.id).await.unwrap();
+    assert_eq!(fetched.kind, DeviceKind::Thermometer { temperature: 21.5 });
+
+    let devices = app.client.get_devices(kitchen.room.id).await.unwrap();
+    assert_eq!(devices.len(), 2);
+
+    let query = ListQuery { sort: Some(SortBy::Name), limit: Some(1), ..Default::default() };
+    let page = app.client.get_devices_page(kitchen.room.id, &query).await.unwrap();
+    assert_eq!(page.items[0].name, "Kettle");
+    assert!(page.next_cursor.is_some());
+
+    let query = ListQuery { name: Some("therm".to_string()), ..Default::default() };
+    let matching: Vec<_> = app.client.devices_stream(kitchen.room.id, query).try_collect().await.unwrap();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].id, kitchen.thermometer.id);
+}
+
+#[tokio::test]
+async fn patches_device_properties() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    let patch = DevicePatch { is_on: Some(true), power: Some(2000.0), ..Default::default() };
+    let patched = app.client
+        .patch_device(kitchen.room.id, kitchen.socket.id, &patch, Some(&kitchen.socket.etag()))
+        .await
+        .unwrap();
+    assert_eq!(patched.kind, DeviceKind::Socket { is_on: true, power: 2000.0 });
+
+    let err = app.client
+        .patch_device(kitchen.room.id, kitchen.socket.id, &patch, Some(&kitchen.socket.etag()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::PreconditionFailed), "{err:?}");
+
+    let patch = DevicePatch { is_on: Some(true), ..Default::default() };
+    let err = app.client.patch_device(kitchen.room.id, kitchen.thermometer.id, &patch, None).await.unwrap_err();
+    let Error::Validation(errors) = err else {
+        panic!("expected a validation error, got {err:?}");
+    };
+    assert_eq!(errors[0].field, "is_on");
+}
+
+#[tokio::test]
+async fn replaces_device_kind() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    let replacement = thermometer("Kettle", 95.0);
+    let replaced = app.client
+        .replace_device(kitchen.room.id, kitchen.socket.id, &replacement, Some(&kitchen.socket.etag()))
+        .await
+        .unwrap();
+    assert_eq!(replaced.id, kitchen.socket.id);
+    assert_eq!(replaced.kind, DeviceKind::Thermometer { temperature: 95.0 });
+
+    let id = uuid::Uuid::new_v4();
+    let created = app.client.replace_device(kitchen.room.id, id, &socket("Toaster", false, 0.0), None).await.unwrap();
+    assert_eq!(created.id, id);
+    assert_eq!(app.client.get_devices(kitchen.room.id).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn moves_device_between_rooms() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+    let hall = add_room(&app.client, kitchen.house.id, "Hall").await;
+
+    let moved = app.client.move_device(kitchen.room.id, kitchen.socket.id, hall.id).await.unwrap();
+    assert_eq!(moved.id, kitchen.socket.id);
+    assert_eq!(moved.room_id, hall.id);
+
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.socket.id).await, Err(Error::NotFound)));
+    assert_eq!(app.client.get_device(hall.id, kitchen.socket.id).await.unwrap().name, "Kettle");
+
+    let err = app.client.move_device(hall.id, kitchen.socket.id, uuid::Uuid::new_v4()).await.unwrap_err();
+    let Error::Validation(errors) = err else {
+        panic!("expected a validation error, got {err:?}");
+    };
+    assert_eq!(errors[0].field, "room_id");
+
+    let other_house = add_house(&app.client, "Cottage").await;
+    let porch = add_room(&app.client, other_house.id, "Porch").await;
+    add_device(&app.client, porch.id, socket("Kettle", false, 0.0)).await;
+
+    let err = app.client.move_device(hall.id, kitchen.socket.id, porch.id).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { .. }), "{err:?}");
+}
+
+#[tokio::test]
+async fn duplicate_device_name_is_a_conflict() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    let err = app.client.add_device(kitchen.room.id, &socket("Kettle", true, 10.0)).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { field: Some(ref field), .. } if field == "name"), "{err:?}");
+
+    let patch = DevicePatch { name: Some("Thermometer".to_string()), ..Default::default() };
+    let err = app.client.patch_device(kitchen.room.id, kitchen.socket.id, &patch, None).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { .. }), "{err:?}");
+}
+
+#[tokio::test]
+async fn deletes_device() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    app.client.delete_device(kitchen.room.id, kitchen.socket.id, None).await.unwrap();
+
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.socket.id).await, Err(Error::NotFound)));
+    assert_eq!(app.client.get_devices(kitchen.room.id).await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn deletes_device_only_at_expected_etag() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    let patch = DevicePatch { is_on: Some(true), ..Default::default() };
+    let patched = app.client.patch_device(kitchen.room.id, kitchen.socket.id, &patch, None).await.unwrap();
+
+    let err = app.client
+        .delete_device(kitchen.room.id, kitchen.socket.id, Some(&kitchen.socket.etag()))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::PreconditionFailed), "{err:?}");
+
+    app.client.delete_device(kitchen.room.id, kitchen.socket.id, Some(&patched.etag())).await.unwrap();
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.socket.id).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn missing_device_is_not_found() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+    let id = uuid::Uuid::new_v4();
+
+    assert!(matches!(app.client.get_device(kitchen.room.id, id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.delete_device(kitchen.room.id, id, None).await, Err(Error::NotFound)));
+    assert!(matches!(
+        app.client.patch_device(kitchen.room.id, id, &DevicePatch::default(), None).await,
+        Err(Error::NotFound)
+    ));
+    assert!(matches!(app.client.move_device(kitchen.room.id, id, kitchen.room.id).await, Err(Error::NotFound)));
+
+    // Devices are only found through the room holding them
+    let hall = add_room(&app.client, kitchen.house.id, "Hall").await;
+    assert!(matches!(app.client.get_device(hall.id, kitchen.socket.id).await, Err(Error::NotFound)));
+
+    let missing_room = uuid::Uuid::new_v4();
+    assert!(matches!(app.client.add_device(missing_room, &socket("Lamp", true, 40.0)).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_devices(missing_room).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn listing_devices_of_missing_room_is_not_found() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+
+    // Filters and paging must not turn a missing room into an empty page
+    let missing_room = uuid::Uuid::new_v4();
+    let query = ListQuery { name: Some("Kettle".to_string()), limit: Some(1), ..Default::default() };
+    assert!(matches!(app.client.get_devices_page(missing_room, &query).await, Err(Error::NotFound)));
+
+    let first = app.client.get_devices_page(kitchen.room.id, &query).await.unwrap();
+    assert_eq!(first.items.len(), 1);
+
+    app.client.delete_room(kitchen.room.id, None).await.unwrap();
+
+    let query = ListQuery { updated_since: Some(kitchen.room.created_at), ..Default::default() };
+    assert!(matches!(app.client.get_devices_page(kitchen.room.id, &query).await, Err(Error::NotFound)));
+    let stream: Result<Vec<_>, _> = app.client.devices_stream(kitchen.room.id, ListQuery::default()).try_collect().await;
+    assert!(matches!(stream, Err(Error::NotFound)), "{stream:?}");
+}
@@ -0,0 +1,114 @@
+mod common;
+
+use client::{Error, HousePatch, NewHouse};
+use common::{add_device, add_house, add_room, kitchen, socket, spawn_app};
+
+#[tokio::test]
+async fn creates_and_lists_houses() {
+    let app = spawn_app().await;
+
+    assert!(app.client.get_houses().await.unwrap().is_empty());
+
+    let first = add_house(&app.client, "First").await;
+    let second = add_house(&app.client, "Second").await;
+
+    assert_eq!(first.name, "First");
+    assert_eq!(first.created_at, first.updated_at);
+
+    let fetched = app.client.get_house(first.id).await.unwrap();
+    assert_eq!(fetched.id, first.id);
+    assert_eq!(fetched.name, first.name);
+
+    let mut ids: Vec<_> = app.client.get_houses().await.unwrap().into_iter().map(|house| house.id).collect();
+    ids.sort();
+    let mut expected = [first.id, second.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn patches_house_name() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Old").await;
+
+    let unchanged = app.client.patch_house(house.id, &HousePatch { name: None }).await.unwrap();
+    assert_eq!(unchanged.updated_at, house.updated_at);
+
+    let patch = HousePatch { name: Some("New".to_string()) };
+    let patched = app.client.patch_house(house.id, &patch).await.unwrap();
+
+    assert_eq!(patched.name, "New");
+    assert!(patched.updated_at > house.updated_at);
+    assert_eq!(app.client.get_house(house.id).await.unwrap().name, "New");
+}
+
+#[tokio::test]
+async fn replace_creates_missing_house_then_updates_it() {
+    let app = spawn_app().await;
+    let id = uuid::Uuid::new_v4();
+
+    let created = app.client.replace_house(id, &NewHouse { name: "Created".to_string() }).await.unwrap();
+    assert_eq!(created.id, id);
+
+    let replaced = app.client.replace_house(id, &NewHouse { name: "Replaced".to_string() }).await.unwrap();
+    assert_eq!(replaced.id, id);
+    assert_eq!(replaced.name, "Replaced");
+    assert_eq!(replaced.created_at, created.created_at);
+}
+
+#[tokio::test]
+async fn duplicate_house_name_is_a_conflict() {
+    let app = spawn_app().await;
+    add_house(&app.client, "Taken").await;
+    let other = add_house(&app.client, "Other").await;
+
+    let err = app.client.add_house(&NewHouse { name: "Taken".to_string() }).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { field: Some(ref field), .. } if field == "name"), "{err:?}");
+
+    let patch = HousePatch { name: Some("Taken".to_string()) };
+    let err = app.client.patch_house(other.id, &patch).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { .. }), "{err:?}");
+}
+
+#[tokio::test]
+async fn invalid_house_is_rejected() {
+    let app = spawn_app().await;
+
+    let err = app.client.add_house(&NewHouse { name: " ".to_string() }).await.unwrap_err();
+    let Error::Validation(errors) = err else {
+        panic!("expected a validation error, got {err:?}");
+    };
+    assert_eq!(errors[0].field, "name");
+}
+
+#[tokio::test]
+async fn missing_house_is_not_found() {
+    let app = spawn_app().await;
+    let id = uuid::Uuid::new_v4();
+
+    assert!(matches!(app.client.get_house(id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.patch_house(id, &HousePatch::default()).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.delete_house(id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_rooms(id).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn delete_house_cascades_to_rooms_and_devices() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+    let bedroom = add_room(&app.client, kitchen.house.id, "Bedroom").await;
+    let lamp = add_device(&app.client, bedroom.id, socket("Lamp", true, 40.0)).await;
+    let neighbour = add_house(&app.client, "Neighbour").await;
+
+    app.client.delete_house(kitchen.house.id).await.unwrap();
+
+    assert!(matches!(app.client.get_house(kitchen.house.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_room(kitchen.room.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_room(bedroom.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.socket.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_device(bedroom.id, lamp.id).await, Err(Error::NotFound)));
+
+    let houses = app.client.get_houses().await.unwrap();
+    assert_eq!(houses.len(), 1);
+    assert_eq!(houses[0].id, neighbour.id);
+}
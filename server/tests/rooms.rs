@@ -0,0 +1,187 @@
+mod common;
+
+use client::{Error, ListQuery, NewRoom, ReplaceRoom, RoomPatch, SortBy};
+use common::{add_device, add_house, add_room, kitchen, socket, spawn_app};
+use futures::TryStreamExt;
+
+#[tokio::test]
+async fn creates_and_lists_rooms() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+
+    let kitchen = add_room(&app.client, house.id, "Kitchen").await;
+    let bedroom = add_room(&app.client, house.id, "Bedroom").await;
+
+    assert_eq!(kitchen.house_id, house.id);
+
+    let fetched = app.client.get_room(kitchen.id).await.unwrap();
+    assert_eq!(fetched.name, "Kitchen");
+
+    let rooms = app.client.get_rooms(house.id).await.unwrap();
+    assert_eq!(rooms.len(), 2);
+    assert!(rooms.iter().any(|room| room.id == kitchen.id));
+    assert!(rooms.iter().any(|room| room.id == bedroom.id));
+}
+
+#[tokio::test]
+async fn pages_through_rooms() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+
+    for name in ["Attic", "Bathroom", "Cellar", "Den", "Entrance"] {
+        add_room(&app.client, house.id, name).await;
+    }
+
+    let query = ListQuery { sort: Some(SortBy::Name), limit: Some(2), ..Default::default() };
+    let first = app.client.get_rooms_page(house.id, &query).await.unwrap();
+    let names: Vec<_> = first.items.iter().map(|room| room.name.as_str()).collect();
+    assert_eq!(names, ["Attic", "Bathroom"]);
+
+    let query = ListQuery { cursor: first.next_cursor, ..query };
+    let second = app.client.get_rooms_page(house.id, &query).await.unwrap();
+    let names: Vec<_> = second.items.iter().map(|room| room.name.as_str()).collect();
+    assert_eq!(names, ["Cellar", "Den"]);
+
+    let query = ListQuery { sort: Some(SortBy::Name), limit: Some(2), ..Default::default() };
+    let all: Vec<_> = app.client.rooms_stream(house.id, query).try_collect().await.unwrap();
+    assert_eq!(all.len(), 5);
+
+    let query = ListQuery { name: Some("ttI".to_string()), ..Default::default() };
+    let matching: Vec<_> = app.client.rooms_stream(house.id, query).try_collect().await.unwrap();
+    assert_eq!(matching.len(), 1);
+    assert_eq!(matching[0].name, "Attic");
+}
+
+#[tokio::test]
+async fn filters_non_ascii_names_ignoring_case() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+
+    for name in ["Гостиная", "Спальня", "Bügelzimmer"] {
+        add_room(&app.client, house.id, name).await;
+    }
+
+    for (filter, expected) in [("гОСТ", "Гостиная"), ("ÜGEL", "Bügelzimmer")] {
+        let query = ListQuery { name: Some(filter.to_string()), ..Default::default() };
+        let matching = app.client.get_rooms_page(house.id, &query).await.unwrap().items;
+        let names: Vec<_> = matching.iter().map(|room| room.name.as_str()).collect();
+        assert_eq!(names, [expected], "filter {filter}");
+    }
+}
+
+#[tokio::test]
+async fn patches_room_with_etag() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+    let room = add_room(&app.client, house.id, "Kitchen").await;
+
+    let patch = RoomPatch { name: Some("Dining room".to_string()) };
+    let patched = app.client.patch_room(room.id, &patch, Some(&room.etag())).await.unwrap();
+    assert_eq!(patched.name, "Dining room");
+
+    let patch = RoomPatch { name: Some("Pantry".to_string()) };
+    let err = app.client.patch_room(room.id, &patch, Some(&room.etag())).await.unwrap_err();
+    assert!(matches!(err, Error::PreconditionFailed), "{err:?}");
+
+    let patched = app.client.patch_room(room.id, &patch, None).await.unwrap();
+    assert_eq!(patched.name, "Pantry");
+}
+
+#[tokio::test]
+async fn deletes_room_only_at_expected_etag() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+    let room = add_room(&app.client, house.id, "Kitchen").await;
+
+    let patch = RoomPatch { name: Some("Pantry".to_string()) };
+    let patched = app.client.patch_room(room.id, &patch, None).await.unwrap();
+
+    let err = app.client.delete_room(room.id, Some(&room.etag())).await.unwrap_err();
+    assert!(matches!(err, Error::PreconditionFailed), "{err:?}");
+    assert_eq!(app.client.get_room(room.id).await.unwrap().name, "Pantry");
+
+    app.client.delete_room(room.id, Some(&patched.etag())).await.unwrap();
+    assert!(matches!(app.client.get_room(room.id).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn replaces_room_into_another_house() {
+    let app = spawn_app().await;
+    let home = add_house(&app.client, "Home").await;
+    let cottage = add_house(&app.client, "Cottage").await;
+    let room = add_room(&app.client, home.id, "Kitchen").await;
+
+    let replacement = ReplaceRoom { house_id: cottage.id, name: "Porch".to_string() };
+    let replaced = app.client.replace_room(room.id, &replacement, Some(&room.etag())).await.unwrap();
+
+    assert_eq!(replaced.id, room.id);
+    assert_eq!(replaced.house_id, cottage.id);
+    assert!(app.client.get_rooms(home.id).await.unwrap().is_empty());
+
+    let err = app.client.replace_room(room.id, &replacement, Some(&room.etag())).await.unwrap_err();
+    assert!(matches!(err, Error::PreconditionFailed), "{err:?}");
+
+    let id = uuid::Uuid::new_v4();
+    let created = app.client.replace_room(id, &replacement, None).await;
+    assert!(matches!(created, Err(Error::Conflict { .. })), "{created:?}");
+
+    let replacement = ReplaceRoom { house_id: home.id, name: "Garage".to_string() };
+    let created = app.client.replace_room(id, &replacement, None).await.unwrap();
+    assert_eq!(created.id, id);
+}
+
+#[tokio::test]
+async fn replace_into_missing_house_is_invalid() {
+    let app = spawn_app().await;
+    let house = add_house(&app.client, "Home").await;
+    let room = add_room(&app.client, house.id, "Kitchen").await;
+
+    let replacement = ReplaceRoom { house_id: uuid::Uuid::new_v4(), name: "Kitchen".to_string() };
+    let err = app.client.replace_room(room.id, &replacement, None).await.unwrap_err();
+
+    let Error::Validation(errors) = err else {
+        panic!("expected a validation error, got {err:?}");
+    };
+    assert_eq!(errors[0].field, "house_id");
+}
+
+#[tokio::test]
+async fn duplicate_room_name_is_a_conflict_within_a_house() {
+    let app = spawn_app().await;
+    let home = add_house(&app.client, "Home").await;
+    let cottage = add_house(&app.client, "Cottage").await;
+    add_room(&app.client, home.id, "Kitchen").await;
+
+    let err = app.client.add_room(home.id, &NewRoom { name: "Kitchen".to_string() }).await.unwrap_err();
+    assert!(matches!(err, Error::Conflict { field: Some(ref field), .. } if field == "name"), "{err:?}");
+
+    add_room(&app.client, cottage.id, "Kitchen").await;
+}
+
+#[tokio::test]
+async fn missing_room_is_not_found() {
+    let app = spawn_app().await;
+    let id = uuid::Uuid::new_v4();
+
+    assert!(matches!(app.client.get_room(id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.patch_room(id, &RoomPatch::default(), None).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.delete_room(id, None).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.add_room(id, &NewRoom { name: "Kitchen".to_string() }).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn delete_room_cascades_to_devices() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+    let hall = add_room(&app.client, kitchen.house.id, "Hall").await;
+    let lamp = add_device(&app.client, hall.id, socket("Lamp", true, 40.0)).await;
+
+    app.client.delete_room(kitchen.room.id, None).await.unwrap();
+
+    assert!(matches!(app.client.get_room(kitchen.room.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.socket.id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_device(kitchen.room.id, kitchen.thermometer.id).await, Err(Error::NotFound)));
+
+    assert_eq!(app.client.get_device(hall.id, lamp.id).await.unwrap().name, "Lamp");
+    assert_eq!(app.client.get_rooms(kitchen.house.id).await.unwrap().len(), 1);
+}
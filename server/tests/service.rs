@@ -0,0 +1,85 @@
+mod common;
+
+use axum::Router;
+use client::{Client, Error, HealthStatus, ServiceTransport};
+use common::{add_house, add_room, kitchen, spawn_app};
+use server::{repository::MemoryRepository, AppState};
+
+#[tokio::test]
+async fn reports_health_and_version() {
+    let app = spawn_app().await;
+
+    let health = app.client.health().await.unwrap();
+    assert_eq!(health.status, HealthStatus::Ok);
+    assert!(health.checks.is_empty());
+
+    let readiness = app.client.readiness().await.unwrap();
+    assert_eq!(readiness.status, HealthStatus::Ok);
+    assert!(readiness.checks.iter().all(|check| check.status == HealthStatus::Ok));
+
+    let version = app.client.version().await.unwrap();
+    assert_eq!(version.name, "server");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+}
+
+#[tokio::test]
+async fn reports_house_contents() {
+    let app = spawn_app().await;
+    let kitchen = kitchen(&app.client).await;
+    add_room(&app.client, kitchen.house.id, "Attic").await;
+
+    let report = app.client.get_report(kitchen.house.id).await.unwrap();
+    assert_eq!(report.house_id, kitchen.house.id);
+    assert_eq!(report.total_rooms, 2);
+    assert_eq!(report.total_devices, 2);
+
+    let rooms: Vec<_> = report.rooms.iter().map(|room| room.name.as_str()).collect();
+    assert_eq!(rooms, ["Attic", "Kitchen"]);
+
+    let devices: Vec<_> = report.rooms[1].devices.iter().map(|device| device.name.as_str()).collect();
+    assert_eq!(devices, ["Kettle", "Thermometer"]);
+
+    let text = app.client.get_report_text(kitchen.house.id).await.unwrap();
+    assert_eq!(
+        text,
+        "House: Home\n\
+        \x20 Room: Attic\n\
+        \x20   (no devices)\n\
+        \x20 Room: Kitchen\n\
+        \x20   - Kettle (socket, off, 0 W)\n\
+        \x20   - Thermometer (thermometer, 21.5 °C)\n\
+        Total: 2 rooms, 2 devices"
+    );
+}
+
+#[tokio::test]
+async fn report_of_missing_house_is_not_found() {
+    let app = spawn_app().await;
+    add_house(&app.client, "Home").await;
+
+    let house_id = uuid::Uuid::new_v4();
+    assert!(matches!(app.client.get_report(house_id).await, Err(Error::NotFound)));
+    assert!(matches!(app.client.get_report_text(house_id).await, Err(Error::NotFound)));
+}
+
+#[tokio::test]
+async fn joins_paths_to_base_url_with_a_path() {
+    let router = server::router(AppState::new(MemoryRepository::new()));
+    let transport = ServiceTransport::new(Router::new().nest("/api/v1", router));
+
+    for base in ["http://localhost/api/v1", "http://localhost/api/v1/"] {
+        let client = Client::with_transport(base.to_string(), transport.clone()).unwrap();
+
+        let house = add_house(&client, "Home").await;
+        assert_eq!(client.get_house(house.id).await.unwrap().name, "Home");
+        client.delete_house(house.id).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn rejects_invalid_base_url() {
+    for base in ["localhost:4000", "not a url", "ftp://localhost", "http://localhost/?page=1"] {
+        let result = Client::new(base.to_string());
+        assert!(matches!(result, Err(Error::InvalidBaseUrl { ref url, .. }) if url == base), "{base}");
+    }
+}
@@ -1,14 +1,46 @@
+mod validate;
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
 use serde::{Serialize, Deserialize};
 
+pub use validate::{FieldError, Validate, MAX_NAME_LENGTH};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct House {
+    pub id: uuid::Uuid,
     pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewHouse {
+    pub name: String
+}
+
+/// Partial house update, absent fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HousePatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Room {
     pub id: uuid::Uuid,
-    pub name: String
+    pub house_id: uuid::Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>
+}
+
+impl Room {
+    /// Entity tag of the current room version, see [`etag`].
+    pub fn etag(&self) -> String {
+        etag(self.updated_at)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -16,19 +48,300 @@ pub struct NewRoom {
     pub name: String
 }
 
+/// Partial room update, absent fields are left unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RoomPatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>
+}
+
+/// Full room representation for PUT, which may also move the room to another house.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceRoom {
+    pub house_id: uuid::Uuid,
+    pub name: String
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Device {
     pub id: uuid::Uuid,
     pub room_id: uuid::Uuid,
-    pub name: String
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: DeviceKind,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>
+}
+
+impl Device {
+    /// Entity tag of the current device version, see [`etag`].
+    pub fn etag(&self) -> String {
+        etag(self.updated_at)
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewDevice {
-    pub name: String
+    pub name: String,
+    #[serde(flatten)]
+    pub kind: DeviceKind
+}
+
+/// Partial device update, absent fields are left unchanged.
+///
+/// Kind-specific properties may only be set on devices of that kind,
+/// the kind itself is changed by a full replacement.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DevicePatch {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub is_on: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub power: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f64>
+}
+
+impl DevicePatch {
+    /// Applies the kind-specific properties of the patch to `kind`.
+    pub fn apply_to_kind(&self, kind: &DeviceKind) -> Result<DeviceKind, Vec<FieldError>> {
+        let mut errors = Vec::new();
+
+        let mut inapplicable = |field: &str, value_is_set: bool| {
+            if value_is_set {
+                errors.push(FieldError::new(field, format!("not applicable to a {}", kind.name())));
+            }
+        };
+
+        let result = match *kind {
+            DeviceKind::Socket { is_on, power } => {
+                inapplicable("temperature", self.temperature.is_some());
+
+                DeviceKind::Socket {
+                    is_on: self.is_on.unwrap_or(is_on),
+                    power: self.power.unwrap_or(power)
+                }
+            },
+            DeviceKind::Thermometer { temperature } => {
+                inapplicable("is_on", self.is_on.is_some());
+                inapplicable("power", self.power.is_some());
+
+                DeviceKind::Thermometer {
+                    temperature: self.temperature.unwrap_or(temperature)
+                }
+            }
+        };
+
+        if errors.is_empty() {
+            Ok(result)
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Target of a device move.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MoveDevice {
+    pub room_id: uuid::Uuid
+}
+
+/// Device kind with its kind-specific properties.
+///
+/// Serialized inline into the device object with a `kind` tag, e.g.
+/// `{"name": "Lamp", "kind": "socket", "is_on": true, "power": 40.0}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeviceKind {
+    Socket {
+        is_on: bool,
+        /// Power draw in watts.
+        power: f64
+    },
+    Thermometer {
+        /// Temperature in degrees Celsius.
+        temperature: f64
+    }
+}
+
+impl DeviceKind {
+    /// Values of the `kind` tag, one per variant.
+    pub const NAMES: [&'static str; 2] = ["socket", "thermometer"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Socket { .. } => "socket",
+            Self::Thermometer { .. } => "thermometer"
+        }
+    }
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Socket { is_on, power } => {
+                let state = if *is_on { "on" } else { "off" };
+                write!(f, "socket, {state}, {power} W")
+            },
+            Self::Thermometer { temperature } => write!(f, "thermometer, {temperature} °C")
+        }
+    }
+}
+
+/// Strong HTTP entity tag derived from the `updated_at` timestamp.
+///
+/// Sent by the server in `ETag` and expected back in `If-Match` for
+/// conditional updates and deletes.
+pub fn etag(updated_at: DateTime<Utc>) -> String {
+    format!("\"{}\"", updated_at.timestamp_micros())
+}
+
+/// Default number of entries in a listing page.
+pub const DEFAULT_PAGE_LIMIT: u32 = 50;
+
+/// Maximum number of entries in a listing page.
+pub const MAX_PAGE_LIMIT: u32 = 200;
+
+/// Filters, sorting and pagination of room and device listings.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListQuery {
+    /// Only return entries changed at or after this moment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_since: Option<DateTime<Utc>>,
+    /// Only return entries whose name contains this substring, ignoring case
+    /// of any letter, not only ASCII ones.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort: Option<SortBy>,
+    /// Opaque cursor from [`Page::next_cursor`] of the previous page.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<String>,
+    /// Page size, [`DEFAULT_PAGE_LIMIT`] if absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u32>
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortBy {
+    Name,
+    #[default]
+    CreatedAt
+}
+
+/// One page of a listing.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor of the next page, absent on the last one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>
+}
+
+/// House report: every room with its devices plus totals.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub house_id: uuid::Uuid,
+    pub house: String,
+    pub rooms: Vec<RoomReport>,
+    pub total_rooms: usize,
+    pub total_devices: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomReport {
+    pub id: uuid::Uuid,
+    pub name: String,
+    pub devices: Vec<Device>
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "House: {}", self.house)?;
+
+        for room in &self.rooms {
+            writeln!(f, "  Room: {}", room.name)?;
+
+            if room.devices.is_empty() {
+                writeln!(f, "    (no devices)")?;
+            }
+
+            for device in &room.devices {
+                writeln!(f, "    - {} ({})", device.name, device.kind)?;
+            }
+        }
+
+        write!(f, "Total: {} rooms, {} devices", self.total_rooms, self.total_devices)
+    }
+}
+
+/// Body of `/healthz` and `/readyz`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Health {
+    pub status: HealthStatus,
+    /// Individual readiness checks, empty for liveness.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub checks: Vec<HealthCheck>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthCheck {
+    pub name: String,
+    pub status: HealthStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Ok,
+    Unavailable
+}
+
+/// Body of `/version`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Version {
+    pub name: String,
+    pub version: String,
+    /// Commit the server was built from, if built from a git checkout.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub git_hash: Option<String>
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Error {
-    pub error: String
+    pub error: String,
+    /// `Unknown` when the server sends none.
+    #[serde(default)]
+    pub code: ErrorCode,
+    /// Request field the error refers to, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Per-field errors of a rejected payload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<FieldError>,
+    /// Reference to the server log entry of an internal error.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_id: Option<String>
+}
+
+/// Machine-readable error kind.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Internal,
+    NotFound,
+    BadRequest,
+    Conflict,
+    ValidationFailed,
+    PreconditionFailed,
+    PayloadTooLarge,
+    Timeout,
+    /// Code not known to this version of the crate.
+    #[serde(other)]
+    #[default]
+    Unknown
 }
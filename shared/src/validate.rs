@@ -0,0 +1,153 @@
+use serde::{Serialize, Deserialize};
+
+use crate::{
+    DeviceKind, DevicePatch, HousePatch, ListQuery, MoveDevice, NewDevice, NewHouse, NewRoom, ReplaceRoom, RoomPatch,
+    MAX_PAGE_LIMIT
+};
+
+/// Maximum length of house, room and device names in characters.
+pub const MAX_NAME_LENGTH: usize = 255;
+
+/// Lowest physically possible temperature in degrees Celsius.
+const ABSOLUTE_ZERO: f64 = -273.15;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: impl Into<String>) -> Self {
+        Self { field: field.to_string(), message: message.into() }
+    }
+}
+
+/// Validation rules of API payloads, shared by the server and the client.
+pub trait Validate {
+    fn validate(&self) -> Result<(), Vec<FieldError>>;
+}
+
+impl Validate for NewHouse {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        validate_name(&self.name, &mut errors);
+        into_result(errors)
+    }
+}
+
+impl Validate for NewRoom {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        validate_name(&self.name, &mut errors);
+        into_result(errors)
+    }
+}
+
+impl Validate for HousePatch {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            validate_name(name, &mut errors);
+        }
+        into_result(errors)
+    }
+}
+
+impl Validate for RoomPatch {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            validate_name(name, &mut errors);
+        }
+        into_result(errors)
+    }
+}
+
+impl Validate for ReplaceRoom {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        validate_name(&self.name, &mut errors);
+        into_result(errors)
+    }
+}
+
+impl Validate for NewDevice {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        validate_name(&self.name, &mut errors);
+        validate_kind(&self.kind, &mut errors);
+        into_result(errors)
+    }
+}
+
+impl Validate for DevicePatch {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(name) = &self.name {
+            validate_name(name, &mut errors);
+        }
+        if let Some(power) = self.power {
+            validate_power(power, &mut errors);
+        }
+        if let Some(temperature) = self.temperature {
+            validate_temperature(temperature, &mut errors);
+        }
+        into_result(errors)
+    }
+}
+
+/// Nothing to check beyond the shape, the target room is looked up by the server.
+impl Validate for MoveDevice {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        Ok(())
+    }
+}
+
+impl Validate for ListQuery {
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        if let Some(limit) = self.limit && (limit == 0 || limit > MAX_PAGE_LIMIT) {
+            errors.push(FieldError::new("limit", format!("must be between 1 and {MAX_PAGE_LIMIT}")));
+        }
+        if let Some(name) = &self.name && name.chars().count() > MAX_NAME_LENGTH {
+            errors.push(FieldError::new("name", format!("must be at most {MAX_NAME_LENGTH} characters")));
+        }
+        into_result(errors)
+    }
+}
+
+fn validate_name(name: &str, errors: &mut Vec<FieldError>) {
+    if name.trim().is_empty() {
+        errors.push(FieldError::new("name", "must not be blank"));
+    } else if name.chars().count() > MAX_NAME_LENGTH {
+        errors.push(FieldError::new("name", format!("must be at most {MAX_NAME_LENGTH} characters")));
+    }
+}
+
+fn validate_kind(kind: &DeviceKind, errors: &mut Vec<FieldError>) {
+    match *kind {
+        DeviceKind::Socket { power, .. } => validate_power(power, errors),
+        DeviceKind::Thermometer { temperature } => validate_temperature(temperature, errors)
+    }
+}
+
+fn validate_power(power: f64, errors: &mut Vec<FieldError>) {
+    if !power.is_finite() || power < 0.0 {
+        errors.push(FieldError::new("power", "must be a non-negative number"));
+    }
+}
+
+fn validate_temperature(temperature: f64, errors: &mut Vec<FieldError>) {
+    if !temperature.is_finite() || temperature < ABSOLUTE_ZERO {
+        errors.push(FieldError::new("temperature", "must not be below absolute zero"));
+    }
+}
+
+fn into_result(errors: Vec<FieldError>) -> Result<(), Vec<FieldError>> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}